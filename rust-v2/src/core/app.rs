@@ -1,19 +1,21 @@
-use crate::event::Action;
+use crate::core::form_controller::FormController;
 use crate::date_input::DateTimeInput;
+use crate::event::Action;
 use crate::event_emitter::{AppEvent, EventEmitter};
-use crate::core::form_controller::FormController;
+use crate::form_step::FormStep;
 use crate::input_manager::InputManager;
 use crate::node::Node;
-use crate::renderer::Renderer;
-use crate::terminal::Terminal;
+use crate::password_input::PasswordInput;
+use crate::renderer::{RenderMode, Renderer};
+use crate::terminal::Backend;
 use crate::text_input::TextInput;
 use crate::theme::Theme;
 use crate::validators;
 use crate::view_state::ViewState;
-use crate::form_step::FormStep;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use std::io;
 use std::time::{Duration, Instant};
+use zeroize::Zeroize;
 
 const ERROR_TIMEOUT: Duration = Duration::from_secs(2);
 
@@ -25,11 +27,14 @@ pub struct App {
     view_state: ViewState,
     theme: Theme,
     pub should_exit: bool,
+    pub submitted: bool,
+    /// Set by Ctrl+Z; the event loop stops the process and clears it.
+    pub suspend_requested: bool,
 }
 
 impl App {
     pub fn new() -> Self {
-        let app = Self {
+        Self {
             form: FormController::new(build_step()),
             renderer: Renderer::new(),
             input_manager: InputManager::new(),
@@ -37,9 +42,19 @@ impl App {
             view_state: ViewState::new(),
             theme: Theme::default_theme(),
             should_exit: false,
-        };
+            submitted: false,
+            suspend_requested: false,
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
 
-        app
+    pub fn with_render_mode(mut self, mode: RenderMode) -> Self {
+        self.renderer.set_mode(mode);
+        self
     }
 
     pub fn tick(&mut self) -> bool {
@@ -55,7 +70,7 @@ impl App {
         processed_any
     }
 
-    pub fn render(&mut self, terminal: &mut Terminal) -> io::Result<()> {
+    pub fn render(&mut self, terminal: &mut dyn Backend) -> io::Result<()> {
         self.renderer
             .render(&self.form.step, &self.view_state, &self.theme, terminal)
     }
@@ -68,6 +83,14 @@ impl App {
         self.event_emitter.emit(AppEvent::Key(key_event));
     }
 
+    pub fn handle_mouse(&mut self, mouse_event: MouseEvent) {
+        self.event_emitter.emit(AppEvent::Mouse(mouse_event));
+    }
+
+    pub fn handle_paste(&mut self, text: String) {
+        self.event_emitter.emit(AppEvent::Paste(text));
+    }
+
     fn dispatch_event(&mut self, event: AppEvent) {
         match event {
             AppEvent::Key(key_event) => {
@@ -77,13 +100,27 @@ impl App {
                     self.event_emitter.emit(AppEvent::InputKey(key_event));
                 }
             }
-            AppEvent::InputKey(key_event) => self.handle_input_key(key_event),
-            AppEvent::Action(action) => self.handle_action(action),
-            AppEvent::ClearErrorMessage { id } => {
-                self.form.handle_clear_error_message(&id, &mut self.view_state)
+            AppEvent::InputKey(key_event) => {
+                self.renderer.follow_focus();
+                self.handle_input_key(key_event)
             }
+            AppEvent::Mouse(mouse_event) => self.handle_mouse_event(mouse_event),
+            AppEvent::Paste(mut text) => {
+                self.form.handle_paste(
+                    &text,
+                    &mut self.view_state,
+                    &mut self.event_emitter,
+                    ERROR_TIMEOUT,
+                );
+                // It may have been a password.
+                text.zeroize();
+            }
+            AppEvent::Action(action) => self.handle_action(action),
+            AppEvent::ClearErrorMessage { id } => self
+                .form
+                .handle_clear_error_message(&id, &mut self.view_state),
+            AppEvent::FocusChanged { .. } => self.renderer.follow_focus(),
             AppEvent::InputChanged { .. }
-            | AppEvent::FocusChanged { .. }
             | AppEvent::ValidationFailed { .. }
             | AppEvent::Submitted
             | AppEvent::Rerender => {}
@@ -93,28 +130,33 @@ impl App {
     fn handle_action(&mut self, action: Action) {
         match action {
             Action::Exit => self.should_exit = true,
-            Action::NextInput => self
-                .form
-                .move_focus(1, &mut self.view_state, &mut self.event_emitter),
-            Action::PrevInput => self
-                .form
-                .move_focus(-1, &mut self.view_state, &mut self.event_emitter),
+            Action::Suspend => self.suspend_requested = true,
+            Action::NextInput => {
+                self.form
+                    .move_focus(1, &mut self.view_state, &mut self.event_emitter)
+            }
+            Action::PrevInput => {
+                self.form
+                    .move_focus(-1, &mut self.view_state, &mut self.event_emitter)
+            }
             Action::Submit => self.handle_submit(),
-            Action::DeleteWord => self
-                .form
-                .handle_delete_word(false, &mut self.view_state, &mut self.event_emitter),
-            Action::DeleteWordForward => self
-                .form
-                .handle_delete_word(true, &mut self.view_state, &mut self.event_emitter),
+            Action::DeleteWord => {
+                self.form
+                    .handle_delete_word(false, &mut self.view_state, &mut self.event_emitter)
+            }
+            Action::DeleteWordForward => {
+                self.form
+                    .handle_delete_word(true, &mut self.view_state, &mut self.event_emitter)
+            }
         }
     }
 
     fn handle_submit(&mut self) {
-        if self.form.handle_submit(
-            &mut self.view_state,
-            &mut self.event_emitter,
-            ERROR_TIMEOUT,
-        ) {
+        if self
+            .form
+            .handle_submit(&mut self.view_state, &mut self.event_emitter, ERROR_TIMEOUT)
+        {
+            self.submitted = true;
             self.should_exit = true;
         }
     }
@@ -123,30 +165,90 @@ impl App {
         self.form
             .handle_input_key(key_event, &mut self.view_state, &mut self.event_emitter);
     }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(hit) = self.renderer.hit_test(mouse_event.column, mouse_event.row) {
+                    self.form.handle_click(
+                        &hit.id,
+                        hit.offset,
+                        &mut self.view_state,
+                        &mut self.event_emitter,
+                    );
+                }
+            }
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let delta = if mouse_event.kind == MouseEventKind::ScrollUp {
+                    -1
+                } else {
+                    1
+                };
+                // The input under the pointer gets the wheel first; over
+                // anything else, or an input without a use for it, the
+                // form scrolls.
+                let handled = self
+                    .renderer
+                    .hit_test(mouse_event.column, mouse_event.row)
+                    .is_some_and(|hit| {
+                        self.form.handle_scroll(
+                            &hit.id,
+                            delta,
+                            &mut self.view_state,
+                            &mut self.event_emitter,
+                        )
+                    });
+                if !handled {
+                    self.renderer.scroll_by(delta as isize);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 fn build_step() -> FormStep {
     FormStep {
         prompt: "Please fill the form:".to_string(),
-        hint: Some("Press Tab/Shift+Tab to navigate, Enter to submit, Esc to exit".to_string()),
+        hint: Some(
+            "Press [bold]Tab[/]/[bold]Shift+Tab[/] to navigate, [bold]Enter[/] to submit, [bold]Esc[/] to exit"
+                .to_string(),
+        ),
         nodes: vec![
-            Node::input(
-                TextInput::new("username", "Username")
-                    .with_validator(validators::required())
-                    .with_validator(validators::min_length(3)),
-            ),
-            Node::input(
-                TextInput::new("email", "Email")
-                    .with_validator(validators::required())
-                    .with_validator(validators::email()),
+            Node::section(
+                "Account",
+                vec![
+                    Node::input(
+                        TextInput::new("username", "Username")
+                            .with_validator(validators::required())
+                            .with_validator(validators::min_length(3)),
+                    ),
+                    Node::input(
+                        TextInput::new("email", "Email")
+                            .with_validator(validators::required())
+                            .with_validator(validators::email()),
+                    ),
+                    Node::input(
+                        PasswordInput::new("password", "Password")
+                            .with_validator(validators::required())
+                            .with_validator(validators::min_length(8)),
+                    ),
+                ],
             ),
-            Node::input(
-                TextInput::new("password", "Password")
-                    .with_validator(validators::required())
-                    .with_validator(validators::min_length(8)),
+            Node::boxed(
+                Some("Dates"),
+                vec![
+                    Node::input(DateTimeInput::new("birthdate", "Birth Date", "DD/MM/YYYY")),
+                    Node::input(DateTimeInput::new("meeting_time", "Meeting Time", "HH:mm")),
+                ],
             ),
-            Node::input(DateTimeInput::new("birthdate", "Birth Date", "DD/MM/YYYY")),
-            Node::input(DateTimeInput::new("meeting_time", "Meeting Time", "HH:mm")),
         ],
+        render_mode: None,
     }
 }
@@ -6,4 +6,5 @@ pub enum Action {
     PrevInput,
     DeleteWord,        // Ctrl+Backspace
     DeleteWordForward, // Ctrl+Delete
+    Suspend,           // Ctrl+Z
 }
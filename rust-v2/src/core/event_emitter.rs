@@ -1,5 +1,5 @@
 use crate::event::Action;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, MouseEvent};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -7,12 +7,26 @@ use std::time::{Duration, Instant};
 pub enum AppEvent {
     Key(KeyEvent),
     InputKey(KeyEvent),
+    Mouse(MouseEvent),
+    Paste(String),
     Action(Action),
-    InputChanged { id: String, value: String },
-    FocusChanged { from: Option<String>, to: Option<String> },
-    ValidationFailed { id: String, error: String },
+    /// `value` is `None` for secret inputs such as passwords.
+    InputChanged {
+        id: String,
+        value: Option<String>,
+    },
+    FocusChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ValidationFailed {
+        id: String,
+        error: String,
+    },
     Submitted,
-    ClearErrorMessage { id: String },
+    ClearErrorMessage {
+        id: String,
+    },
     Rerender,
 }
 
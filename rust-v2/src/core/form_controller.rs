@@ -1,33 +1,29 @@
 use crate::event_emitter::{AppEvent, EventEmitter};
 use crate::form_step::{FormStep, FormStepExt};
-use crate::input::KeyResult;
-use crate::node::Node;
+use crate::input::{Input, KeyResult};
+use crate::node;
 use crate::view_state::{ErrorDisplay, ViewState};
 use crossterm::event::KeyEvent;
 use std::time::Duration;
 
 pub struct FormController {
     pub step: FormStep,
-    input_indices: Vec<usize>,
+    /// Tree paths of the inputs, in focus order.
+    input_paths: Vec<Vec<usize>>,
     focused_pos: Option<usize>,
 }
 
 impl FormController {
     pub fn new(step: FormStep) -> Self {
-        let input_indices: Vec<usize> = step
-            .nodes
-            .iter()
-            .enumerate()
-            .filter_map(|(i, node)| if matches!(node, Node::Input(_)) { Some(i) } else { None })
-            .collect();
+        let input_paths = node::input_paths(&step.nodes);
 
         let mut controller = Self {
             step,
-            input_indices,
+            input_paths,
             focused_pos: None,
         };
 
-        if !controller.input_indices.is_empty() {
+        if !controller.input_paths.is_empty() {
             controller.set_focus_without_events(Some(0));
         }
 
@@ -44,23 +40,107 @@ impl FormController {
         view_state: &mut ViewState,
         event_emitter: &mut EventEmitter,
     ) {
-        if let Some(current_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[current_pos]) {
-                let before = input.value();
-                let result = input.handle_key(key_event.code, key_event.modifiers);
-                let after = input.value();
-                if before != after {
-                    event_emitter.emit(AppEvent::InputChanged {
-                        id: input.id().clone(),
-                        value: after,
-                    });
-                }
-                if matches!(result, KeyResult::Submit) {
-                    event_emitter.emit(AppEvent::Action(crate::event::Action::Submit));
-                }
-                self.clear_error_message(view_state, event_emitter);
-                self.validate_active_input(view_state, event_emitter);
+        let result = self.edit_focused_input(view_state, event_emitter, |input| {
+            input.handle_key(key_event.code, key_event.modifiers)
+        });
+        if matches!(result, Some(KeyResult::Submit)) {
+            event_emitter.emit(AppEvent::Action(crate::event::Action::Submit));
+        }
+    }
+
+    /// Mouse wheel over the input `id`. Returns false when the input has no
+    /// use for it, so that the form can scroll instead. An input that does
+    /// react gets the focus, as with a click.
+    pub fn handle_scroll(
+        &mut self,
+        id: &str,
+        delta: i32,
+        view_state: &mut ViewState,
+        event_emitter: &mut EventEmitter,
+    ) -> bool {
+        let Some(pos) = self.find_input_pos_by_id(id) else {
+            return false;
+        };
+        let Some(input) = self.input_at_mut(pos) else {
+            return false;
+        };
+
+        let before = input.revision();
+        if input.handle_scroll(delta) == KeyResult::NotHandled {
+            return false;
+        }
+        let changed = (input.revision() != before).then(|| changed_event(input));
+
+        if self.focused_pos != Some(pos) {
+            self.validate_active_input(view_state, event_emitter);
+            self.update_focus(Some(pos), event_emitter);
+        }
+        if let Some(event) = changed {
+            event_emitter.emit(event);
+            self.clear_error_message(view_state, event_emitter);
+            self.validate_active_input(view_state, event_emitter);
+        }
+        true
+    }
+
+    /// Hands pasted text to the focused input in one go, so it is validated
+    /// and reported once. An input that refuses the paste shows its error.
+    pub fn handle_paste(
+        &mut self,
+        text: &str,
+        view_state: &mut ViewState,
+        event_emitter: &mut EventEmitter,
+        error_timeout: Duration,
+    ) {
+        let Some(current_pos) = self.focused_pos else {
+            return;
+        };
+        let Some(input) = self.input_at_mut(current_pos) else {
+            return;
+        };
+
+        let before = input.revision();
+        if input.handle_paste(text) == KeyResult::NotHandled {
+            if let Some(err) = input.error().map(str::to_string) {
+                let id = input.id().clone();
+                view_state.set_error_display(id.clone(), ErrorDisplay::InlineMessage);
+                event_emitter.cancel_clear_error_message(&id);
+                event_emitter.emit_after(
+                    AppEvent::ClearErrorMessage { id: id.clone() },
+                    error_timeout,
+                );
+                event_emitter.emit(AppEvent::ValidationFailed { id, error: err });
             }
+            return;
+        }
+
+        if input.revision() != before {
+            event_emitter.emit(changed_event(input));
+        }
+        self.clear_error_message(view_state, event_emitter);
+        self.validate_active_input(view_state, event_emitter);
+    }
+
+    /// Focuses the clicked input and, when the click was on its content,
+    /// lets the input react to the position (move the cursor, pick a segment).
+    pub fn handle_click(
+        &mut self,
+        id: &str,
+        offset: Option<usize>,
+        view_state: &mut ViewState,
+        event_emitter: &mut EventEmitter,
+    ) {
+        let Some(pos) = self.find_input_pos_by_id(id) else {
+            return;
+        };
+        if self.focused_pos != Some(pos) {
+            self.validate_active_input(view_state, event_emitter);
+            self.update_focus(Some(pos), event_emitter);
+        }
+        if let Some(offset) = offset
+            && let Some(input) = self.input_at_mut(pos)
+        {
+            input.handle_click(offset);
         }
     }
 
@@ -70,24 +150,20 @@ impl FormController {
         view_state: &mut ViewState,
         event_emitter: &mut EventEmitter,
     ) {
-        if let Some(current_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[current_pos]) {
-                let before = input.value();
-                if forward {
-                    input.delete_word_forward();
-                } else {
-                    input.delete_word();
-                }
-                let after = input.value();
-                if before != after {
-                    event_emitter.emit(AppEvent::InputChanged {
-                        id: input.id().clone(),
-                        value: after,
-                    });
-                }
-                self.clear_error_message(view_state, event_emitter);
-                self.validate_active_input(view_state, event_emitter);
+        if let Some(current_pos) = self.focused_pos
+            && let Some(input) = self.input_at_mut(current_pos)
+        {
+            let before = input.revision();
+            if forward {
+                input.delete_word_forward();
+            } else {
+                input.delete_word();
             }
+            if input.revision() != before {
+                event_emitter.emit(changed_event(input));
+            }
+            self.clear_error_message(view_state, event_emitter);
+            self.validate_active_input(view_state, event_emitter);
         }
     }
 
@@ -97,14 +173,14 @@ impl FormController {
         view_state: &mut ViewState,
         event_emitter: &mut EventEmitter,
     ) {
-        if self.input_indices.is_empty() {
+        if self.input_paths.is_empty() {
             return;
         }
 
         self.validate_active_input(view_state, event_emitter);
 
         let current_pos = self.focused_pos.unwrap_or(0);
-        let len = self.input_indices.len() as isize;
+        let len = self.input_paths.len() as isize;
         let next_pos = (current_pos as isize + direction + len) % len;
         self.update_focus(Some(next_pos as usize), event_emitter);
     }
@@ -115,48 +191,48 @@ impl FormController {
         event_emitter: &mut EventEmitter,
         error_timeout: Duration,
     ) -> bool {
-        if let Some(current_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[current_pos]) {
-                if let Err(err) = input.validate() {
-                    let id = input.id().clone();
-                    input.set_error(Some(err.clone()));
-                    view_state.set_error_display(id.clone(), ErrorDisplay::InlineMessage);
-                    event_emitter.cancel_clear_error_message(&id);
-                    event_emitter.emit_after(
-                        AppEvent::ClearErrorMessage { id: id.clone() },
-                        error_timeout,
-                    );
-                    event_emitter.emit(AppEvent::ValidationFailed { id, error: err });
-                    return false;
+        if let Some(current_pos) = self.focused_pos
+            && let Some(input) = self.input_at_mut(current_pos)
+        {
+            if let Err(err) = input.validate() {
+                let id = input.id().clone();
+                input.set_error(Some(err.clone()));
+                view_state.set_error_display(id.clone(), ErrorDisplay::InlineMessage);
+                event_emitter.cancel_clear_error_message(&id);
+                event_emitter.emit_after(
+                    AppEvent::ClearErrorMessage { id: id.clone() },
+                    error_timeout,
+                );
+                event_emitter.emit(AppEvent::ValidationFailed { id, error: err });
+                return false;
+            }
+
+            input.set_error(None);
+            view_state.clear_error_display(input.id());
+            event_emitter.cancel_clear_error_message(input.id());
+
+            let next_pos = current_pos + 1;
+            if next_pos < self.input_paths.len() {
+                self.update_focus(Some(next_pos), event_emitter);
+            } else {
+                let errors = self.step.validate_all();
+                if errors.is_empty() {
+                    event_emitter.emit(AppEvent::Submitted);
+                    return true;
                 }
 
-                input.set_error(None);
-                view_state.clear_error_display(input.id());
-                event_emitter.cancel_clear_error_message(input.id());
+                self.apply_validation_errors(&errors, view_state, event_emitter, error_timeout);
+                for (id, error) in &errors {
+                    event_emitter.emit(AppEvent::ValidationFailed {
+                        id: id.clone(),
+                        error: error.clone(),
+                    });
+                }
 
-                let next_pos = current_pos + 1;
-                if next_pos < self.input_indices.len() {
-                    self.update_focus(Some(next_pos), event_emitter);
-                } else {
-                    let errors = self.step.validate_all();
-                    if errors.is_empty() {
-                        event_emitter.emit(AppEvent::Submitted);
-                        return true;
-                    }
-
-                    self.apply_validation_errors(&errors, view_state, event_emitter, error_timeout);
-                    for (id, error) in &errors {
-                        event_emitter.emit(AppEvent::ValidationFailed {
-                            id: id.clone(),
-                            error: error.clone(),
-                        });
-                    }
-
-                    if let Some(first_id) = errors.first().map(|(id, _)| id.clone()) {
-                        if let Some(pos) = self.find_input_pos_by_id(&first_id) {
-                            self.update_focus(Some(pos), event_emitter);
-                        }
-                    }
+                if let Some(first_id) = errors.first().map(|(id, _)| id.clone())
+                    && let Some(pos) = self.find_input_pos_by_id(&first_id)
+                {
+                    self.update_focus(Some(pos), event_emitter);
                 }
             }
         }
@@ -164,10 +240,10 @@ impl FormController {
     }
 
     pub fn handle_clear_error_message(&mut self, id: &str, view_state: &mut ViewState) {
-        if let Some(pos) = self.find_input_pos_by_id(id) {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[pos]) {
-                view_state.clear_error_display(input.id());
-            }
+        if let Some(pos) = self.find_input_pos_by_id(id)
+            && let Some(input) = self.input_at_mut(pos)
+        {
+            view_state.clear_error_display(input.id());
         }
     }
 
@@ -178,51 +254,75 @@ impl FormController {
         event_emitter: &mut EventEmitter,
         error_timeout: Duration,
     ) {
-        for idx in &self.input_indices {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(*idx) {
-                if let Some((_, error)) = errors.iter().find(|(id, _)| id == input.id()) {
-                    let id = input.id().clone();
-                    input.set_error(Some(error.clone()));
-                    view_state.set_error_display(id.clone(), ErrorDisplay::InlineMessage);
-                    event_emitter.cancel_clear_error_message(&id);
-                    event_emitter.emit_after(
-                        AppEvent::ClearErrorMessage { id },
-                        error_timeout,
-                    );
-                } else {
-                    input.set_error(None);
-                    view_state.clear_error_display(input.id());
-                    event_emitter.cancel_clear_error_message(input.id());
-                }
+        for input in node::inputs_mut(&mut self.step.nodes) {
+            if let Some((_, error)) = errors.iter().find(|(id, _)| id == input.id()) {
+                let id = input.id().clone();
+                input.set_error(Some(error.clone()));
+                view_state.set_error_display(id.clone(), ErrorDisplay::InlineMessage);
+                event_emitter.cancel_clear_error_message(&id);
+                event_emitter.emit_after(AppEvent::ClearErrorMessage { id }, error_timeout);
+            } else {
+                input.set_error(None);
+                view_state.clear_error_display(input.id());
+                event_emitter.cancel_clear_error_message(input.id());
             }
         }
     }
 
-    fn validate_active_input(&mut self, view_state: &mut ViewState, event_emitter: &mut EventEmitter) {
-        if let Some(current_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[current_pos]) {
-                match input.validate() {
-                    Ok(()) => {
-                        input.set_error(None);
-                        view_state.clear_error_display(input.id());
-                    }
-                    Err(err) => {
-                        let id = input.id().clone();
-                        input.set_error(Some(err.clone()));
-                        view_state.clear_error_display(&id);
-                        event_emitter.emit(AppEvent::ValidationFailed { id, error: err });
-                    }
+    /// Runs `edit` on the focused input. A changed value is reported and
+    /// revalidated; otherwise the input's error state is left alone.
+    fn edit_focused_input(
+        &mut self,
+        view_state: &mut ViewState,
+        event_emitter: &mut EventEmitter,
+        edit: impl FnOnce(&mut dyn Input) -> KeyResult,
+    ) -> Option<KeyResult> {
+        let current_pos = self.focused_pos?;
+        let input = self.input_at_mut(current_pos)?;
+
+        let before = input.revision();
+        let result = edit(input);
+        if input.revision() != before {
+            event_emitter.emit(changed_event(input));
+            self.clear_error_message(view_state, event_emitter);
+            self.validate_active_input(view_state, event_emitter);
+        }
+        Some(result)
+    }
+
+    fn validate_active_input(
+        &mut self,
+        view_state: &mut ViewState,
+        event_emitter: &mut EventEmitter,
+    ) {
+        if let Some(current_pos) = self.focused_pos
+            && let Some(input) = self.input_at_mut(current_pos)
+        {
+            match input.validate() {
+                Ok(()) => {
+                    input.set_error(None);
+                    view_state.clear_error_display(input.id());
+                }
+                Err(err) => {
+                    let id = input.id().clone();
+                    input.set_error(Some(err.clone()));
+                    view_state.clear_error_display(&id);
+                    event_emitter.emit(AppEvent::ValidationFailed { id, error: err });
                 }
             }
         }
     }
 
-    fn clear_error_message(&mut self, view_state: &mut ViewState, event_emitter: &mut EventEmitter) {
-        if let Some(current_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[current_pos]) {
-                view_state.clear_error_display(input.id());
-                event_emitter.cancel_clear_error_message(input.id());
-            }
+    fn clear_error_message(
+        &mut self,
+        view_state: &mut ViewState,
+        event_emitter: &mut EventEmitter,
+    ) {
+        if let Some(current_pos) = self.focused_pos
+            && let Some(input) = self.input_at_mut(current_pos)
+        {
+            view_state.clear_error_display(input.id());
+            event_emitter.cancel_clear_error_message(input.id());
         }
     }
 
@@ -230,55 +330,168 @@ impl FormController {
         let from_id = self.focused_pos.and_then(|pos| self.input_id_at(pos));
         let to_id = new_pos.and_then(|pos| self.input_id_at(pos));
 
-        if let Some(old_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[old_pos]) {
-                input.set_focused(false);
-            }
+        if let Some(old_pos) = self.focused_pos
+            && let Some(input) = self.input_at_mut(old_pos)
+        {
+            input.set_focused(false);
         }
 
-        if let Some(pos) = new_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[pos]) {
-                input.set_focused(true);
-            }
+        if let Some(pos) = new_pos
+            && let Some(input) = self.input_at_mut(pos)
+        {
+            input.set_focused(true);
         }
 
         self.focused_pos = new_pos;
         if from_id != to_id {
-            event_emitter.emit(AppEvent::FocusChanged { from: from_id, to: to_id });
+            event_emitter.emit(AppEvent::FocusChanged {
+                from: from_id,
+                to: to_id,
+            });
         }
     }
 
     fn set_focus_without_events(&mut self, new_pos: Option<usize>) {
-        if let Some(old_pos) = self.focused_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[old_pos]) {
-                input.set_focused(false);
-            }
+        if let Some(old_pos) = self.focused_pos
+            && let Some(input) = self.input_at_mut(old_pos)
+        {
+            input.set_focused(false);
         }
 
-        if let Some(pos) = new_pos {
-            if let Some(Node::Input(input)) = self.step.nodes.get_mut(self.input_indices[pos]) {
-                input.set_focused(true);
-            }
+        if let Some(pos) = new_pos
+            && let Some(input) = self.input_at_mut(pos)
+        {
+            input.set_focused(true);
         }
 
         self.focused_pos = new_pos;
     }
 
+    fn input_at_mut(&mut self, pos: usize) -> Option<&mut dyn Input> {
+        let path = self.input_paths.get(pos)?;
+        node::node_at_mut(&mut self.step.nodes, path)?.as_input_mut()
+    }
+
     fn input_id_at(&self, pos: usize) -> Option<String> {
-        self.input_indices
+        self.input_paths
             .get(pos)
-            .and_then(|idx| self.step.nodes.get(*idx))
+            .and_then(|path| node::node_at(&self.step.nodes, path))
             .and_then(|node| node.as_input())
             .map(|input| input.id().clone())
     }
 
     fn find_input_pos_by_id(&self, id: &str) -> Option<usize> {
-        self.input_indices.iter().position(|idx| {
-            self.step
-                .nodes
-                .get(*idx)
+        self.input_paths.iter().position(|path| {
+            node::node_at(&self.step.nodes, path)
                 .and_then(|node| node.as_input())
                 .is_some_and(|input| input.id() == id)
         })
     }
 }
+
+/// Reports an edit of `input`. Secret values stay inside the input; their
+/// listeners only learn that something changed.
+fn changed_event(input: &dyn Input) -> AppEvent {
+    AppEvent::InputChanged {
+        id: input.id().clone(),
+        value: (!input.is_secret()).then(|| input.value()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::Node;
+    use crate::password_input::PasswordInput;
+    use crate::text_input::{PasteNewlines, TextInput};
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use std::time::Instant;
+
+    fn changes(input: Node, keys: &str) -> Vec<Option<String>> {
+        let mut form = FormController::new(FormStep {
+            prompt: String::new(),
+            hint: None,
+            nodes: vec![input],
+            render_mode: None,
+        });
+        let mut view_state = ViewState::new();
+        let mut events = EventEmitter::new();
+        for ch in keys.chars() {
+            let key = KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+            form.handle_input_key(key, &mut view_state, &mut events);
+        }
+        // Moving the cursor changes nothing.
+        let left = KeyEvent::new(KeyCode::Left, KeyModifiers::NONE);
+        form.handle_input_key(left, &mut view_state, &mut events);
+
+        std::iter::from_fn(|| events.next_ready(Instant::now()))
+            .filter_map(|event| match event {
+                AppEvent::InputChanged { value, .. } => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn paste(input: TextInput, text: &str) -> (FormController, Vec<AppEvent>) {
+        let mut form = FormController::new(FormStep {
+            prompt: String::new(),
+            hint: None,
+            nodes: vec![Node::input(input)],
+            render_mode: None,
+        });
+        let mut view_state = ViewState::new();
+        let mut events = EventEmitter::new();
+        form.handle_paste(text, &mut view_state, &mut events, Duration::from_secs(5));
+        let events = std::iter::from_fn(|| events.next_ready(Instant::now())).collect();
+        (form, events)
+    }
+
+    #[test]
+    fn a_paste_is_one_edit() {
+        let (mut form, events) = paste(TextInput::new("token", "Token"), "abc\ndef\n");
+        let changes: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                AppEvent::InputChanged { value, .. } => Some(value.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, vec![Some("abcdef".to_string())]);
+
+        // `set_value` is not used here, so only the paste moved the revision.
+        assert_eq!(form.input_at_mut(0).unwrap().revision(), 1);
+    }
+
+    #[test]
+    fn a_rejected_paste_reports_an_error_and_no_change() {
+        let input = TextInput::new("token", "Token").with_paste_newlines(PasteNewlines::Reject);
+        let (mut form, events) = paste(input, "abc\ndef");
+        assert!(
+            !events
+                .iter()
+                .any(|event| matches!(event, AppEvent::InputChanged { .. }))
+        );
+        assert!(events.iter().any(|event| matches!(
+            event,
+            AppEvent::ValidationFailed { error, .. } if error == "Pasted text must be a single line"
+        )));
+        let input = form.input_at_mut(0).unwrap();
+        assert_eq!(input.value(), "");
+        assert_eq!(input.revision(), 0);
+    }
+
+    #[test]
+    fn reports_each_edit_once() {
+        let values = changes(Node::input(TextInput::new("name", "Name")), "ab");
+        assert_eq!(values, vec![Some("a".to_string()), Some("ab".to_string())]);
+    }
+
+    #[test]
+    fn secret_values_stay_out_of_change_events() {
+        let values = changes(
+            Node::input(PasswordInput::new("password", "Password")),
+            "ab",
+        );
+        assert_eq!(values, vec![None, None]);
+    }
+}
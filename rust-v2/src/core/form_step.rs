@@ -1,10 +1,13 @@
 use crate::input::{Input, NodeId};
-use crate::node::Node;
+use crate::node::{self, Node};
+use crate::renderer::RenderMode;
 
 pub struct FormStep {
     pub prompt: String,
     pub hint: Option<String>,
     pub nodes: Vec<Node>,
+    /// Overrides the app-wide render mode for this step.
+    pub render_mode: Option<RenderMode>,
 }
 
 pub trait FormStepExt {
@@ -16,31 +19,27 @@ pub trait FormStepExt {
 
 impl FormStepExt for FormStep {
     fn find_input(&self, id: &str) -> Option<&dyn Input> {
-        self.nodes
-            .iter()
-            .filter_map(|node| node.as_input())
+        node::inputs(&self.nodes)
+            .into_iter()
             .find(|input| input.id() == id)
     }
 
     fn find_input_mut(&mut self, id: &str) -> Option<&mut dyn Input> {
-        self.nodes
-            .iter_mut()
-            .filter_map(|node| node.as_input_mut())
+        node::inputs_mut(&mut self.nodes)
+            .into_iter()
             .find(|input| input.id() == id)
     }
 
     fn validate_all(&self) -> Vec<(NodeId, String)> {
-        self.nodes
-            .iter()
-            .filter_map(|node| node.as_input())
+        node::inputs(&self.nodes)
+            .into_iter()
             .filter_map(|input| input.validate().err().map(|err| (input.id().clone(), err)))
             .collect()
     }
 
     fn values(&self) -> Vec<(NodeId, String)> {
-        self.nodes
-            .iter()
-            .filter_map(|node| node.as_input())
+        node::inputs(&self.nodes)
+            .into_iter()
             .map(|input| (input.id().clone(), input.value()))
             .collect()
     }
@@ -60,6 +60,7 @@ impl InputManager {
         self.bind(KeyBinding::ctrl(KeyCode::Backspace), Action::DeleteWord);
         self.bind(KeyBinding::ctrl(KeyCode::Char('w')), Action::DeleteWord); // Alternatywny binding (jak w Bash)
         self.bind(KeyBinding::ctrl(KeyCode::Delete), Action::DeleteWordForward);
+        self.bind(KeyBinding::ctrl(KeyCode::Char('z')), Action::Suspend);
     }
 
     pub fn bind(&mut self, key: KeyBinding, action: Action) {
@@ -0,0 +1,264 @@
+use crate::form_step::{FormStep, FormStepExt};
+use crate::input::{Input, NodeId};
+use crate::markup;
+use crate::node::Node;
+use crate::render_context::RenderContext;
+use crate::terminal::tty::HiddenStdinEcho;
+use crate::theme::Theme;
+use std::io::{self, BufRead, Write};
+use zeroize::Zeroizing;
+
+/// Plain fallback for when there is no terminal: every input becomes a
+/// one-line prompt on `output` and answers are read line by line from
+/// `input`. Nothing is redrawn, so it works with pipes and CI logs.
+pub struct LineMode<R, W> {
+    input: R,
+    output: W,
+    answer_newline: bool,
+    hide_secrets: bool,
+    theme: Theme,
+}
+
+impl<R: BufRead, W: Write> LineMode<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self {
+            input,
+            output,
+            answer_newline: false,
+            hide_secrets: false,
+            theme: Theme::default_theme(),
+        }
+    }
+
+    /// The theme whose symbols mark separators and errors. Styles are not
+    /// used; line mode writes plain text.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Ends the prompt line after every answer. Meant for answers that are
+    /// not typed on a terminal, where nothing echoes the user's Enter.
+    pub fn with_answer_newline(mut self, enabled: bool) -> Self {
+        self.answer_newline = enabled;
+        self
+    }
+
+    /// Turns off echo on stdin while a secret is typed. Meant for answers
+    /// typed on a terminal; fails the question when echo can't be turned off,
+    /// rather than showing the secret.
+    pub fn with_hidden_secrets(mut self, enabled: bool) -> Self {
+        self.hide_secrets = enabled;
+        self
+    }
+
+    /// Asks for every input of `step` in order, repeating a question until
+    /// its answer passes validation. Fails when the answers run out first.
+    pub fn run(&mut self, step: &mut FormStep) -> io::Result<Vec<(NodeId, String)>> {
+        if !step.prompt.is_empty() {
+            writeln!(self.output, "{}", markup::strip(&step.prompt))?;
+        }
+
+        self.ask_nodes(&mut step.nodes)?;
+        Ok(step.values())
+    }
+
+    fn ask_nodes(&mut self, nodes: &mut [Node]) -> io::Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => writeln!(self.output, "{}", markup::strip(text))?,
+                Node::Input(input) => self.ask(input.as_mut())?,
+                Node::Group(children) => self.ask_nodes(children)?,
+                Node::Section { title, children }
+                | Node::Boxed {
+                    title: Some(title),
+                    children,
+                } => {
+                    writeln!(self.output, "{}", markup::strip(title))?;
+                    self.ask_nodes(children)?;
+                }
+                Node::Boxed {
+                    title: None,
+                    children,
+                } => self.ask_nodes(children)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn ask(&mut self, input: &mut dyn Input) -> io::Result<()> {
+        // Empty inputs render their placeholder, e.g. the expected date format.
+        let ctx = RenderContext::new(&self.theme, usize::MAX);
+        let format: String = input
+            .render_content(&ctx)
+            .iter()
+            .map(|s| s.text())
+            .collect();
+        let separator = &self.theme.symbols.separator;
+        let question = if format.trim().is_empty() {
+            format!("{}{}", input.label(), separator)
+        } else {
+            format!("{} ({}){}", input.label(), format, separator)
+        };
+
+        loop {
+            write!(self.output, "{}", question)?;
+            self.output.flush()?;
+
+            // Answers may be passwords; every copy is wiped when dropped.
+            let hidden = (self.hide_secrets && input.is_secret())
+                .then(HiddenStdinEcho::new)
+                .transpose()?;
+            let answer = self.read_answer();
+            drop(hidden);
+            let answer = answer?.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("no answer for \"{}\"", input.label()),
+                )
+            })?;
+            if self.answer_newline {
+                writeln!(self.output)?;
+            }
+
+            input.set_value(answer.to_string());
+            let result = if *Zeroizing::new(input.value()) != *answer {
+                Err(if format.trim().is_empty() {
+                    "Invalid value".to_string()
+                } else {
+                    format!("Expected {}", format)
+                })
+            } else {
+                input.validate()
+            };
+
+            match result {
+                Ok(()) => return Ok(()),
+                Err(err) => writeln!(self.output, "{} {}", self.theme.symbols.error, err)?,
+            }
+        }
+    }
+
+    fn read_answer(&mut self) -> io::Result<Option<Zeroizing<String>>> {
+        let mut line = Zeroizing::new(String::new());
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(Some(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date_input::DateTimeInput;
+    use crate::password_input::PasswordInput;
+    use crate::symbols::Symbols;
+    use crate::text_input::TextInput;
+    use crate::validators;
+
+    fn form(nodes: Vec<Node>) -> FormStep {
+        FormStep {
+            prompt: String::new(),
+            hint: None,
+            nodes,
+            render_mode: None,
+        }
+    }
+
+    fn run(step: &mut FormStep, answers: &str) -> (io::Result<Vec<(NodeId, String)>>, String) {
+        let mut output = Vec::new();
+        let values = LineMode::new(answers.as_bytes(), &mut output).run(step);
+        (values, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn uses_the_symbols_of_its_theme() {
+        let mut step = FormStep {
+            prompt: "[bold]Sign in[/]".to_string(),
+            hint: None,
+            nodes: vec![Node::input(
+                TextInput::new("name", "Name").with_validator(validators::required()),
+            )],
+            render_mode: None,
+        };
+        let mut theme = Theme::default_theme();
+        theme.symbols = Symbols::ascii();
+        theme.symbols.separator = " > ".to_string();
+
+        let mut output = Vec::new();
+        let values = LineMode::new(&b"\nbob\n"[..], &mut output)
+            .with_theme(theme)
+            .run(&mut step)
+            .unwrap();
+
+        assert_eq!(values, vec![("name".to_string(), "bob".to_string())]);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Sign in\nName > x This field is required\nName > "
+        );
+    }
+
+    #[test]
+    fn asks_again_until_the_answer_is_valid() {
+        let name = TextInput::new("name", "Name")
+            .with_validator(validators::required())
+            .with_validator(validators::min_length(3));
+        let date = DateTimeInput::new("date", "Date", "DD/MM/YYYY");
+        let mut step = form(vec![Node::input(name), Node::input(date)]);
+
+        let (values, output) = run(&mut step, "\nbo\nbob\ntomorrow\n01/02/2030\n");
+        assert_eq!(
+            values.unwrap(),
+            vec![
+                ("name".to_string(), "bob".to_string()),
+                ("date".to_string(), "01/02/2030".to_string()),
+            ]
+        );
+        assert_eq!(
+            output,
+            "Name: ✗ This field is required\n\
+             Name: ✗ Minimum length is 3\n\
+             Name: \
+             Date (dd/mm/yyyy): ✗ Expected dd/mm/yyyy\n\
+             Date (dd/mm/yyyy): "
+        );
+    }
+
+    #[test]
+    fn running_out_of_answers_is_an_error() {
+        let name = TextInput::new("name", "Name").with_validator(validators::required());
+        let mut step = form(vec![Node::input(name)]);
+        let (values, output) = run(&mut step, "\n");
+        let err = values.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(err.to_string(), "no answer for \"Name\"");
+        assert_eq!(output, "Name: ✗ This field is required\nName: ");
+
+        let mut step = form(vec![Node::input(TextInput::new("name", "Name"))]);
+        assert!(run(&mut step, "").0.is_err());
+    }
+
+    #[test]
+    fn line_breaks_are_not_part_of_the_answer() {
+        let mut step = form(vec![Node::input(PasswordInput::new(
+            "password", "Password",
+        ))]);
+        let (values, _) = run(&mut step, "hunter2\r\n");
+        assert_eq!(
+            values.unwrap(),
+            vec![("password".to_string(), "hunter2".to_string())]
+        );
+
+        let mut step = form(vec![Node::input(PasswordInput::new(
+            "password", "Password",
+        ))]);
+        let (values, _) = run(&mut step, "hunter2");
+        assert_eq!(
+            values.unwrap(),
+            vec![("password".to_string(), "hunter2".to_string())]
+        );
+    }
+}
@@ -4,4 +4,5 @@ pub mod event_emitter;
 pub mod form_controller;
 pub mod form_step;
 pub mod input_manager;
+pub mod line_mode;
 pub mod view_state;
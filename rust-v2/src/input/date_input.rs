@@ -1,7 +1,7 @@
 use crate::input::{Input, InputBase, KeyResult, NodeId};
+use crate::render_context::RenderContext;
 use crate::span::Span;
 use crate::style::Style;
-use crate::theme;
 use crate::validators::Validator;
 use crossterm::event::{KeyCode, KeyModifiers};
 use unicode_width::UnicodeWidthStr;
@@ -126,10 +126,10 @@ impl DateSegment {
             return true;
         }
         self.value.push(digit);
-        if let Ok(val) = self.value.parse::<u32>() {
-            if val > self.segment_type.max_value() {
-                self.value = digit.to_string();
-            }
+        if let Ok(val) = self.value.parse::<u32>()
+            && val > self.segment_type.max_value()
+        {
+            self.value = digit.to_string();
         }
         true
     }
@@ -148,11 +148,7 @@ impl DateSegment {
             self.placeholder().to_string()
         } else if self.value.len() < len {
             let placeholder = self.placeholder();
-            format!(
-                "{}{}",
-                self.value,
-                &placeholder[self.value.len()..len]
-            )
+            format!("{}{}", self.value, &placeholder[self.value.len()..len])
         } else {
             self.value.clone()
         }
@@ -163,10 +159,10 @@ impl DateSegment {
             return;
         }
         let len = self.segment_type.length();
-        if self.value.len() < len {
-            if let Ok(val) = self.value.parse::<u32>() {
-                self.value = format!("{:0width$}", val, width = len);
-            }
+        if self.value.len() < len
+            && let Ok(val) = self.value.parse::<u32>()
+        {
+            self.value = format!("{:0width$}", val, width = len);
         }
     }
 }
@@ -269,47 +265,18 @@ impl DateTimeInput {
         self.segments.iter().all(|s| s.is_complete())
     }
 
-    fn move_next(&mut self) -> bool {
-        if let Some(segment) = self.segments.get_mut(self.focused_segment) {
-            segment.normalize();
-        }
-
-        if self.focused_segment + 1 < self.segments.len() {
-            self.focused_segment += 1;
-            true
-        } else {
-            false
-        }
-    }
-
-    fn move_prev(&mut self) -> bool {
-        if self.focused_segment > 0 {
-            self.focused_segment -= 1;
-            true
-        } else {
-            false
-        }
-    }
-}
-
-impl Input for DateTimeInput {
-    fn id(&self) -> &NodeId {
-        &self.base.id
-    }
-
-    fn label(&self) -> &str {
-        &self.base.label
-    }
-
-    fn value(&self) -> String {
-        if self.is_complete() {
-            self.format_value()
-        } else {
-            String::new()
+    /// Runs `edit` and bumps the revision if it changed `value()`. Dates
+    /// are short and not secret, so comparing copies is fine here.
+    fn tracked<R>(&mut self, edit: impl FnOnce(&mut Self) -> R) -> R {
+        let before = self.value();
+        let result = edit(self);
+        if self.value() != before {
+            self.base.revision += 1;
         }
+        result
     }
 
-    fn set_value(&mut self, value: String) {
+    fn set_value_impl(&mut self, value: String) {
         if !self.format.is_empty() && value.len() != self.format.len() {
             return;
         }
@@ -345,45 +312,15 @@ impl Input for DateTimeInput {
             pos += len;
         }
 
-        if let Some(trailing) = self.separators.get(self.segments.len()) {
-            if !trailing.is_empty() && value[pos..].starts_with(trailing) {
-                // trailing separator is allowed; nothing to store
-            }
-        }
-    }
-
-    fn is_focused(&self) -> bool {
-        self.base.focused
-    }
-
-    fn set_focused(&mut self, focused: bool) {
-        self.base.focused = focused;
-        if !focused {
-            self.base.error = None;
+        if let Some(trailing) = self.separators.get(self.segments.len())
+            && !trailing.is_empty()
+            && value[pos..].starts_with(trailing)
+        {
+            // trailing separator is allowed; nothing to store
         }
     }
 
-    fn error(&self) -> Option<&str> {
-        self.base.error.as_deref()
-    }
-
-    fn set_error(&mut self, error: Option<String>) {
-        self.base.error = error;
-    }
-
-    fn cursor_pos(&self) -> usize {
-        self.focused_segment
-    }
-
-    fn min_width(&self) -> usize {
-        self.base.min_width
-    }
-
-    fn validators(&self) -> &[Validator] {
-        &self.base.validators
-    }
-
-    fn handle_key(&mut self, code: KeyCode, _modifiers: KeyModifiers) -> KeyResult {
+    fn handle_key_impl(&mut self, code: KeyCode) -> KeyResult {
         match code {
             KeyCode::Char(ch) if ch.is_ascii_digit() => {
                 if let Some(segment) = self.segments.get_mut(self.focused_segment) {
@@ -441,7 +378,140 @@ impl Input for DateTimeInput {
         }
     }
 
-    fn render_content(&self) -> Vec<Span> {
+    fn handle_click_impl(&mut self, offset: usize) -> KeyResult {
+        if self.segments.is_empty() {
+            return KeyResult::NotHandled;
+        }
+
+        // A click on a separator selects the segment after it.
+        let mut end = 0;
+        let mut target = self.segments.len() - 1;
+        for (i, segment) in self.segments.iter().enumerate() {
+            if let Some(separator) = self.separators.get(i) {
+                end += separator.width();
+            }
+            end += segment.display_string().width();
+            if offset < end {
+                target = i;
+                break;
+            }
+        }
+
+        if target != self.focused_segment
+            && let Some(segment) = self.segments.get_mut(self.focused_segment)
+        {
+            segment.normalize();
+        }
+        self.focused_segment = target;
+        KeyResult::Handled
+    }
+
+    fn handle_scroll_impl(&mut self, delta: i32) -> KeyResult {
+        let Some(segment) = self.segments.get_mut(self.focused_segment) else {
+            return KeyResult::NotHandled;
+        };
+        for _ in 0..delta.unsigned_abs() {
+            if delta < 0 {
+                segment.increment();
+            } else {
+                segment.decrement();
+            }
+        }
+        KeyResult::Handled
+    }
+
+    fn move_next(&mut self) -> bool {
+        if let Some(segment) = self.segments.get_mut(self.focused_segment) {
+            segment.normalize();
+        }
+
+        if self.focused_segment + 1 < self.segments.len() {
+            self.focused_segment += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn move_prev(&mut self) -> bool {
+        if self.focused_segment > 0 {
+            self.focused_segment -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Input for DateTimeInput {
+    fn id(&self) -> &NodeId {
+        &self.base.id
+    }
+
+    fn label(&self) -> &str {
+        &self.base.label
+    }
+
+    fn value(&self) -> String {
+        if self.is_complete() {
+            self.format_value()
+        } else {
+            String::new()
+        }
+    }
+
+    fn set_value(&mut self, value: String) {
+        self.tracked(|input| input.set_value_impl(value));
+    }
+
+    fn revision(&self) -> u64 {
+        self.base.revision
+    }
+
+    fn is_focused(&self) -> bool {
+        self.base.focused
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.base.focused = focused;
+        if !focused {
+            self.base.error = None;
+        }
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.base.error.as_deref()
+    }
+
+    fn set_error(&mut self, error: Option<String>) {
+        self.base.error = error;
+    }
+
+    fn cursor_pos(&self) -> usize {
+        self.focused_segment
+    }
+
+    fn min_width(&self) -> usize {
+        self.base.min_width
+    }
+
+    fn validators(&self) -> &[Validator] {
+        &self.base.validators
+    }
+
+    fn handle_key(&mut self, code: KeyCode, _modifiers: KeyModifiers) -> KeyResult {
+        self.tracked(|input| input.handle_key_impl(code))
+    }
+
+    fn handle_click(&mut self, offset: usize) -> KeyResult {
+        self.tracked(|input| input.handle_click_impl(offset))
+    }
+
+    fn handle_scroll(&mut self, delta: i32) -> KeyResult {
+        self.tracked(|input| input.handle_scroll_impl(delta))
+    }
+
+    fn render_content(&self, ctx: &RenderContext) -> Vec<Span> {
         let mut spans = Vec::new();
 
         for (i, segment) in self.segments.iter().enumerate() {
@@ -450,13 +520,13 @@ impl Input for DateTimeInput {
             }
 
             let mut style = if segment.is_empty() {
-                theme::placeholder_style()
+                ctx.theme.placeholder.clone()
             } else {
                 Style::default()
             };
 
             if i == self.focused_segment && self.base.focused {
-                style = style.merge(&theme::focused_style());
+                style = style.merge(&ctx.theme.focused);
             }
 
             spans.push(Span::new(segment.display_string()).with_style(style));
@@ -470,8 +540,9 @@ impl Input for DateTimeInput {
         }
 
         let content_width = self.display_string().width();
-        if content_width < self.base.min_width {
-            let padding = self.base.min_width - content_width;
+        let min_width = self.base.min_width.min(ctx.width);
+        if content_width < min_width {
+            let padding = min_width - content_width;
             spans.push(Span::new(" ".repeat(padding)));
         }
 
@@ -1,3 +1,4 @@
+use crate::render_context::RenderContext;
 use crate::span::Span;
 use crate::validators::Validator;
 use crossterm::event::{KeyCode, KeyModifiers};
@@ -17,6 +18,15 @@ pub trait Input: Send {
     fn value(&self) -> String;
     fn set_value(&mut self, value: String);
 
+    /// Changes whenever `value()` does, so edits can be spotted without
+    /// copying the value.
+    fn revision(&self) -> u64;
+
+    /// Whether the value must stay out of events and logs, as with passwords.
+    fn is_secret(&self) -> bool {
+        false
+    }
+
     fn is_focused(&self) -> bool;
     fn set_focused(&mut self, focused: bool);
 
@@ -37,7 +47,30 @@ pub trait Input: Send {
 
     fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> KeyResult;
 
-    fn render_content(&self) -> Vec<Span>;
+    /// Pasted text. By default it is typed in character by character,
+    /// skipping line breaks and other control characters.
+    fn handle_paste(&mut self, text: &str) -> KeyResult {
+        let mut result = KeyResult::NotHandled;
+        for ch in text.chars().filter(|ch| !ch.is_control()) {
+            if self.handle_key(KeyCode::Char(ch), KeyModifiers::NONE) == KeyResult::Handled {
+                result = KeyResult::Handled;
+            }
+        }
+        result
+    }
+
+    /// A click on the content, `offset` display columns from its start.
+    fn handle_click(&mut self, _offset: usize) -> KeyResult {
+        KeyResult::NotHandled
+    }
+
+    /// Mouse wheel over the form; `delta` is negative for scrolling up.
+    fn handle_scroll(&mut self, _delta: i32) -> KeyResult {
+        KeyResult::NotHandled
+    }
+
+    /// The value as drawn inside the field, styled from `ctx.theme`.
+    fn render_content(&self, ctx: &RenderContext) -> Vec<Span>;
 
     fn cursor_offset_in_content(&self) -> usize;
 
@@ -52,6 +85,8 @@ pub struct InputBase {
     pub error: Option<String>,
     pub validators: Vec<Validator>,
     pub min_width: usize,
+    /// Bumped by every edit to the value, see `Input::revision`.
+    pub revision: u64,
 }
 
 impl InputBase {
@@ -63,6 +98,7 @@ impl InputBase {
             error: None,
             validators: Vec::new(),
             min_width: 1,
+            revision: 0,
         }
     }
 
@@ -1,5 +1,7 @@
 pub mod date_input;
+#[allow(clippy::module_inception)]
 pub mod input;
+pub mod password_input;
 pub mod text_input;
 pub mod validators;
 
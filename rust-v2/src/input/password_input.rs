@@ -0,0 +1,465 @@
+use crate::input::{Input, InputBase, KeyResult, NodeId};
+use crate::input_manager::KeyBinding;
+use crate::render_context::RenderContext;
+use crate::span::Span;
+use crate::validators::Validator;
+use crossterm::event::{KeyCode, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Room for a typical password, so typing rarely has to grow the buffer.
+const INITIAL_CAPACITY: usize = 64;
+
+/// How a `PasswordInput` shows what was typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Echo {
+    /// One mask character per typed character.
+    Mask(char),
+    /// Nothing at all, not even the length.
+    Off,
+}
+
+impl Default for Echo {
+    fn default() -> Self {
+        Echo::Mask('*')
+    }
+}
+
+/// A text field for secrets. The value is hidden unless revealed with the
+/// reveal key, which lasts until the field loses focus, and its buffer is
+/// wiped when the input is dropped.
+pub struct PasswordInput {
+    base: InputBase,
+    value: Zeroizing<String>,
+    cursor_pos: usize,
+    echo: Echo,
+    reveal_key: Option<KeyBinding>,
+    revealed: bool,
+}
+
+impl PasswordInput {
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            base: InputBase::new(id, label),
+            value: Zeroizing::new(String::with_capacity(INITIAL_CAPACITY)),
+            cursor_pos: 0,
+            echo: Echo::default(),
+            reveal_key: Some(KeyBinding::ctrl(KeyCode::Char('r'))),
+            revealed: false,
+        }
+    }
+
+    pub fn with_min_width(mut self, width: usize) -> Self {
+        self.base = self.base.with_min_width(width);
+        self
+    }
+
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.base = self.base.with_validator(validator);
+        self
+    }
+
+    pub fn with_echo(mut self, echo: Echo) -> Self {
+        self.echo = echo;
+        self
+    }
+
+    /// The key that shows or hides the value, Ctrl+R by default. `None`
+    /// keeps the value hidden for good.
+    pub fn with_reveal_key(mut self, key: Option<KeyBinding>) -> Self {
+        self.reveal_key = key;
+        self
+    }
+
+    pub fn is_revealed(&self) -> bool {
+        self.revealed
+    }
+
+    fn insert_str(&mut self, text: &str) {
+        self.reserve(text.len());
+        let byte_pos = self.byte_offset(self.cursor_pos);
+        self.value.insert_str(byte_pos, text);
+        // A combining mark joins the cluster before it instead of adding one.
+        let inserted_end = byte_pos + text.len();
+        self.cursor_pos = self.value[..inserted_end].graphemes(true).count();
+        self.base.error = None;
+        self.base.revision += 1;
+    }
+
+    /// Makes room for `additional` bytes. `String` would reallocate on its
+    /// own and leave the old bytes behind, so the value moves to a fresh
+    /// buffer here and the old one is wiped as it drops.
+    fn reserve(&mut self, additional: usize) {
+        let needed = self.value.len() + additional;
+        if needed <= self.value.capacity() {
+            return;
+        }
+        let mut grown =
+            Zeroizing::new(String::with_capacity(needed.max(self.value.capacity() * 2)));
+        grown.push_str(&self.value);
+        self.value = grown;
+    }
+
+    fn handle_backspace(&mut self) {
+        if self.cursor_pos == 0 {
+            return;
+        }
+        let start = self.byte_offset(self.cursor_pos - 1);
+        let end = self.byte_offset(self.cursor_pos);
+        self.value.replace_range(start..end, "");
+        self.cursor_pos -= 1;
+        self.base.error = None;
+        self.base.revision += 1;
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.value.graphemes(true).count()
+    }
+
+    /// Byte index where grapheme number `index` starts, or the end of the
+    /// value when there are fewer graphemes.
+    fn byte_offset(&self, index: usize) -> usize {
+        self.value
+            .grapheme_indices(true)
+            .nth(index)
+            .map_or(self.value.len(), |(idx, _)| idx)
+    }
+
+    fn mask_width(&self) -> Option<usize> {
+        match self.echo {
+            _ if self.revealed => None,
+            Echo::Mask(mask) => Some(mask.width().unwrap_or(1).max(1)),
+            Echo::Off => Some(0),
+        }
+    }
+}
+
+impl Input for PasswordInput {
+    fn id(&self) -> &NodeId {
+        &self.base.id
+    }
+
+    fn label(&self) -> &str {
+        &self.base.label
+    }
+
+    /// A copy of the secret. The caller owns it and should wipe it when done.
+    fn value(&self) -> String {
+        self.value.to_string()
+    }
+
+    fn set_value(&mut self, mut value: String) {
+        let mut fresh = Zeroizing::new(String::with_capacity(value.len().max(INITIAL_CAPACITY)));
+        fresh.push_str(&value);
+        value.zeroize();
+        self.value = fresh;
+        self.cursor_pos = self.grapheme_count();
+        self.base.revision += 1;
+    }
+
+    fn revision(&self) -> u64 {
+        self.base.revision
+    }
+
+    fn is_secret(&self) -> bool {
+        true
+    }
+
+    fn is_focused(&self) -> bool {
+        self.base.focused
+    }
+
+    fn set_focused(&mut self, focused: bool) {
+        self.base.focused = focused;
+        if !focused {
+            self.base.error = None;
+            self.revealed = false;
+        }
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.base.error.as_deref()
+    }
+
+    fn set_error(&mut self, error: Option<String>) {
+        self.base.error = error;
+    }
+
+    fn cursor_pos(&self) -> usize {
+        self.cursor_pos
+    }
+
+    fn min_width(&self) -> usize {
+        self.base.min_width
+    }
+
+    fn validators(&self) -> &[Validator] {
+        &self.base.validators
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        // Straight from the buffer, without the copy `value()` would make.
+        for validator in self.validators() {
+            validator(&self.value)?;
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> KeyResult {
+        if self.reveal_key.as_ref() == Some(&KeyBinding::new(code, modifiers)) {
+            self.revealed = !self.revealed;
+            return KeyResult::Handled;
+        }
+        match code {
+            KeyCode::Char(_) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                KeyResult::NotHandled
+            }
+            KeyCode::Char(ch) => {
+                self.insert_str(ch.encode_utf8(&mut [0; 4]));
+                KeyResult::Handled
+            }
+            KeyCode::Backspace => {
+                self.handle_backspace();
+                KeyResult::Handled
+            }
+            KeyCode::Left => {
+                self.cursor_pos = self.cursor_pos.saturating_sub(1);
+                KeyResult::Handled
+            }
+            KeyCode::Right => {
+                self.cursor_pos = (self.cursor_pos + 1).min(self.grapheme_count());
+                KeyResult::Handled
+            }
+            KeyCode::Home => {
+                self.cursor_pos = 0;
+                KeyResult::Handled
+            }
+            KeyCode::End => {
+                self.cursor_pos = self.grapheme_count();
+                KeyResult::Handled
+            }
+            KeyCode::Enter => KeyResult::Submit,
+            _ => KeyResult::NotHandled,
+        }
+    }
+
+    fn handle_paste(&mut self, text: &str) -> KeyResult {
+        // Copying a whole line usually brings its line break along.
+        let text = text.trim_end_matches(['\n', '\r']);
+        if text.contains(['\n', '\r']) {
+            self.base.error = Some("Pasted text must be a single line".to_string());
+            return KeyResult::NotHandled;
+        }
+
+        let text: Zeroizing<String> =
+            Zeroizing::new(text.chars().filter(|ch| !ch.is_control()).collect());
+        if text.is_empty() {
+            return KeyResult::NotHandled;
+        }
+        self.insert_str(&text);
+        KeyResult::Handled
+    }
+
+    fn handle_click(&mut self, offset: usize) -> KeyResult {
+        let count = self.grapheme_count();
+        self.cursor_pos = match self.mask_width() {
+            // Hidden text has no positions to click on.
+            Some(0) => return KeyResult::NotHandled,
+            Some(width) => ((offset + width / 2) / width).min(count),
+            None => {
+                let mut width = 0;
+                let mut pos = 0;
+                for grapheme in self.value.graphemes(true) {
+                    let w = grapheme.width();
+                    // Clicking the right half of a character puts the cursor after it.
+                    if width + w.div_ceil(2) > offset {
+                        break;
+                    }
+                    width += w;
+                    pos += 1;
+                }
+                pos
+            }
+        };
+        KeyResult::Handled
+    }
+
+    fn render_content(&self, _ctx: &RenderContext) -> Vec<Span> {
+        match self.echo {
+            _ if self.revealed => vec![Span::new(self.value.as_str())],
+            Echo::Mask(mask) => vec![Span::new(mask.to_string().repeat(self.grapheme_count()))],
+            Echo::Off => Vec::new(),
+        }
+    }
+
+    fn cursor_offset_in_content(&self) -> usize {
+        match self.mask_width() {
+            Some(width) => self.cursor_pos * width,
+            None => self
+                .value
+                .graphemes(true)
+                .take(self.cursor_pos)
+                .map(|g| g.width())
+                .sum(),
+        }
+    }
+
+    /// Words can't be told apart behind a mask, so this clears everything
+    /// before the cursor, like most password prompts do.
+    fn delete_word(&mut self) {
+        let end = self.byte_offset(self.cursor_pos);
+        self.value.replace_range(..end, "");
+        self.cursor_pos = 0;
+        self.base.error = None;
+        if end > 0 {
+            self.base.revision += 1;
+        }
+    }
+
+    fn delete_word_forward(&mut self) {
+        let start = self.byte_offset(self.cursor_pos);
+        let removed = start < self.value.len();
+        self.value.replace_range(start.., "");
+        self.base.error = None;
+        if removed {
+            self.base.revision += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Theme;
+    use crate::validators;
+
+    fn typed(text: &str) -> PasswordInput {
+        let mut input = PasswordInput::new("password", "Password");
+        input.set_focused(true);
+        for ch in text.chars() {
+            input.handle_key(KeyCode::Char(ch), KeyModifiers::NONE);
+        }
+        input
+    }
+
+    fn content(input: &PasswordInput) -> String {
+        let theme = Theme::default_theme();
+        let ctx = RenderContext::new(&theme, 40);
+        input
+            .render_content(&ctx)
+            .iter()
+            .map(|span| span.text())
+            .collect()
+    }
+
+    fn reveal(input: &mut PasswordInput) -> KeyResult {
+        input.handle_key(KeyCode::Char('r'), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn masks_or_hides_the_value() {
+        let input = typed("hunter2");
+        assert_eq!(content(&input), "*******");
+        assert_eq!(content(&typed("abc").with_echo(Echo::Mask('•'))), "•••");
+        assert_eq!(content(&typed("abc").with_echo(Echo::Off)), "");
+    }
+
+    #[test]
+    fn cursor_offset_follows_what_is_drawn() {
+        let mut input = typed("日本");
+        input.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(input.cursor_offset_in_content(), 1);
+
+        let mut wide = typed("日本").with_echo(Echo::Mask('＊'));
+        wide.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(wide.cursor_offset_in_content(), 2);
+
+        let mut off = typed("日本").with_echo(Echo::Off);
+        off.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(off.cursor_offset_in_content(), 0);
+
+        reveal(&mut input);
+        assert_eq!(input.cursor_offset_in_content(), 2);
+    }
+
+    #[test]
+    fn clicks_map_to_mask_cells() {
+        let mut input = typed("secret").with_echo(Echo::Mask('＊'));
+        assert_eq!(input.handle_click(4), KeyResult::Handled);
+        assert_eq!(input.cursor_pos(), 2);
+        input.handle_click(100);
+        assert_eq!(input.cursor_pos(), 6);
+
+        let mut off = typed("secret").with_echo(Echo::Off);
+        assert_eq!(off.handle_click(0), KeyResult::NotHandled);
+
+        let mut revealed = typed("日本");
+        reveal(&mut revealed);
+        revealed.handle_click(1);
+        assert_eq!(revealed.cursor_pos(), 1);
+    }
+
+    #[test]
+    fn reveal_lasts_until_focus_leaves() {
+        let mut input = typed("hunter2");
+        assert_eq!(reveal(&mut input), KeyResult::Handled);
+        assert!(input.is_revealed());
+        assert_eq!(content(&input), "hunter2");
+
+        input.set_focused(false);
+        assert!(!input.is_revealed());
+        assert_eq!(content(&input), "*******");
+
+        let mut locked = typed("hunter2").with_reveal_key(None);
+        assert_eq!(reveal(&mut locked), KeyResult::NotHandled);
+        assert!(!locked.is_revealed());
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_grapheme() {
+        let mut input = typed("ae\u{301}");
+        assert_eq!(input.cursor_pos(), 2);
+        input.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.value(), "a");
+        assert_eq!(input.cursor_pos(), 1);
+    }
+
+    #[test]
+    fn validators_see_the_value() {
+        let input = |text: &str| {
+            let mut input = PasswordInput::new("password", "Password")
+                .with_validator(validators::min_length(8))
+                .with_validator(validators::regex("[0-9]"));
+            input.set_value(text.to_string());
+            input
+        };
+        assert!(input("short1").validate().is_err());
+        assert!(input("longenough").validate().is_err());
+        assert!(input("longenough1").validate().is_ok());
+    }
+
+    #[test]
+    fn growing_moves_the_value_to_a_fresh_buffer() {
+        let mut input = typed(&"a".repeat(INITIAL_CAPACITY));
+        let before = input.value.as_ptr();
+        input.handle_key(KeyCode::Char('b'), KeyModifiers::NONE);
+
+        assert_ne!(input.value.as_ptr(), before);
+        assert!(input.value.capacity() >= INITIAL_CAPACITY * 2);
+        assert_eq!(input.value.len(), INITIAL_CAPACITY + 1);
+        assert!(input.value.ends_with("ab"));
+    }
+
+    #[test]
+    fn edits_bump_the_revision_and_the_value_is_secret() {
+        let mut input = typed("ab");
+        assert!(input.is_secret());
+        let revision = input.revision();
+        input.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        reveal(&mut input);
+        assert_eq!(input.revision(), revision);
+        input.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+        assert_ne!(input.revision(), revision);
+    }
+}
@@ -1,13 +1,27 @@
 use crate::input::{Input, InputBase, KeyResult, NodeId};
+use crate::render_context::RenderContext;
 use crate::span::Span;
 use crate::validators::Validator;
 use crossterm::event::{KeyCode, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+/// What a `TextInput` does with line breaks in pasted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PasteNewlines {
+    /// Drop them and keep the rest of the text.
+    #[default]
+    Strip,
+    /// Refuse the whole paste and show an error. Line breaks at the very
+    /// end are still dropped silently.
+    Reject,
+}
+
 pub struct TextInput {
     base: InputBase,
     value: String,
     cursor_pos: usize,
+    paste_newlines: PasteNewlines,
 }
 
 impl TextInput {
@@ -16,6 +30,7 @@ impl TextInput {
             base: InputBase::new(id, label),
             value: String::new(),
             cursor_pos: 0,
+            paste_newlines: PasteNewlines::default(),
         }
     }
 
@@ -29,27 +44,48 @@ impl TextInput {
         self
     }
 
+    pub fn with_paste_newlines(mut self, paste_newlines: PasteNewlines) -> Self {
+        self.paste_newlines = paste_newlines;
+        self
+    }
+
     fn handle_char(&mut self, ch: char) {
-        let char_indices: Vec<usize> = self.value.char_indices().map(|(i, _)| i).collect();
-        let byte_pos = if self.cursor_pos >= char_indices.len() {
-            self.value.len()
-        } else {
-            char_indices[self.cursor_pos]
-        };
-        self.value.insert(byte_pos, ch);
-        self.cursor_pos += 1;
+        self.insert_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    fn insert_str(&mut self, text: &str) {
+        let byte_pos = self.byte_offset(self.cursor_pos);
+        self.value.insert_str(byte_pos, text);
+        // A combining mark joins the cluster before it instead of adding one.
+        let inserted_end = byte_pos + text.len();
+        self.cursor_pos = self.value[..inserted_end].graphemes(true).count();
         self.base.error = None;
+        if !text.is_empty() {
+            self.base.revision += 1;
+        }
     }
 
     fn handle_backspace(&mut self) {
         if self.cursor_pos == 0 {
             return;
         }
-        let char_indices: Vec<usize> = self.value.char_indices().map(|(i, _)| i).collect();
-        let byte_pos = char_indices[self.cursor_pos - 1];
-        self.value.remove(byte_pos);
+        let start = self.byte_offset(self.cursor_pos - 1);
+        let end = self.byte_offset(self.cursor_pos);
+        self.value.replace_range(start..end, "");
         self.cursor_pos -= 1;
         self.base.error = None;
+        self.base.revision += 1;
+    }
+
+    fn handle_delete(&mut self) {
+        if self.cursor_pos >= self.grapheme_count() {
+            return;
+        }
+        let start = self.byte_offset(self.cursor_pos);
+        let end = self.byte_offset(self.cursor_pos + 1);
+        self.value.replace_range(start..end, "");
+        self.base.error = None;
+        self.base.revision += 1;
     }
 
     fn move_left(&mut self) -> bool {
@@ -62,7 +98,7 @@ impl TextInput {
     }
 
     fn move_right(&mut self) -> bool {
-        if self.cursor_pos < self.value.chars().count() {
+        if self.cursor_pos < self.grapheme_count() {
             self.cursor_pos += 1;
             true
         } else {
@@ -75,7 +111,7 @@ impl TextInput {
     }
 
     fn move_end(&mut self) {
-        self.cursor_pos = self.value.chars().count();
+        self.cursor_pos = self.grapheme_count();
     }
 
     fn delete_word_impl(&mut self) {
@@ -83,41 +119,66 @@ impl TextInput {
             return;
         }
 
-        let mut chars: Vec<char> = self.value.chars().collect();
+        let graphemes: Vec<&str> = self.value.graphemes(true).collect();
         let mut pos = self.cursor_pos;
 
-        while pos > 0 && chars.get(pos - 1).is_some_and(|c| c.is_whitespace()) {
-            chars.remove(pos - 1);
+        while pos > 0 && is_whitespace(graphemes[pos - 1]) {
             pos -= 1;
         }
 
-        while pos > 0 && chars.get(pos - 1).is_some_and(|c| !c.is_whitespace()) {
-            chars.remove(pos - 1);
+        while pos > 0 && !is_whitespace(graphemes[pos - 1]) {
             pos -= 1;
         }
 
-        self.value = chars.into_iter().collect();
+        let start = self.byte_offset(pos);
+        let end = self.byte_offset(self.cursor_pos);
+        self.value.replace_range(start..end, "");
         self.cursor_pos = pos;
         self.base.error = None;
+        if start < end {
+            self.base.revision += 1;
+        }
     }
 
     fn delete_word_forward_impl(&mut self) {
-        let mut chars: Vec<char> = self.value.chars().collect();
-        let pos = self.cursor_pos;
+        let graphemes: Vec<&str> = self.value.graphemes(true).collect();
+        let mut pos = self.cursor_pos;
 
-        while pos < chars.len() && chars.get(pos).is_some_and(|c| c.is_whitespace()) {
-            chars.remove(pos);
+        while pos < graphemes.len() && is_whitespace(graphemes[pos]) {
+            pos += 1;
         }
 
-        while pos < chars.len() && chars.get(pos).is_some_and(|c| !c.is_whitespace()) {
-            chars.remove(pos);
+        while pos < graphemes.len() && !is_whitespace(graphemes[pos]) {
+            pos += 1;
         }
 
-        self.value = chars.into_iter().collect();
+        let start = self.byte_offset(self.cursor_pos);
+        let end = self.byte_offset(pos);
+        self.value.replace_range(start..end, "");
         self.base.error = None;
+        if start < end {
+            self.base.revision += 1;
+        }
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.value.graphemes(true).count()
+    }
+
+    /// Byte index where grapheme number `index` starts, or the end of the
+    /// value when there are fewer graphemes.
+    fn byte_offset(&self, index: usize) -> usize {
+        self.value
+            .grapheme_indices(true)
+            .nth(index)
+            .map_or(self.value.len(), |(idx, _)| idx)
     }
 }
 
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
 impl Input for TextInput {
     fn id(&self) -> &NodeId {
         &self.base.id
@@ -132,8 +193,13 @@ impl Input for TextInput {
     }
 
     fn set_value(&mut self, value: String) {
-        self.cursor_pos = value.chars().count();
+        self.cursor_pos = value.graphemes(true).count();
         self.value = value;
+        self.base.revision += 1;
+    }
+
+    fn revision(&self) -> u64 {
+        self.base.revision
     }
 
     fn is_focused(&self) -> bool {
@@ -177,6 +243,10 @@ impl Input for TextInput {
                 self.handle_backspace();
                 KeyResult::Handled
             }
+            KeyCode::Delete => {
+                self.handle_delete();
+                KeyResult::Handled
+            }
             KeyCode::Left => {
                 self.move_left();
                 KeyResult::Handled
@@ -198,15 +268,47 @@ impl Input for TextInput {
         }
     }
 
-    fn render_content(&self) -> Vec<Span> {
+    fn handle_paste(&mut self, text: &str) -> KeyResult {
+        // Copying a whole line usually brings its line break along.
+        let text = text.trim_end_matches(['\n', '\r']);
+        if self.paste_newlines == PasteNewlines::Reject && text.contains(['\n', '\r']) {
+            self.base.error = Some("Pasted text must be a single line".to_string());
+            return KeyResult::NotHandled;
+        }
+
+        let text: String = text.chars().filter(|ch| !ch.is_control()).collect();
+        if text.is_empty() {
+            return KeyResult::NotHandled;
+        }
+        self.insert_str(&text);
+        KeyResult::Handled
+    }
+
+    fn handle_click(&mut self, offset: usize) -> KeyResult {
+        let mut width = 0;
+        let mut pos = 0;
+        for grapheme in self.value.graphemes(true) {
+            let w = grapheme.width();
+            // Clicking the right half of a character puts the cursor after it.
+            if width + w.div_ceil(2) > offset {
+                break;
+            }
+            width += w;
+            pos += 1;
+        }
+        self.cursor_pos = pos;
+        KeyResult::Handled
+    }
+
+    fn render_content(&self, _ctx: &RenderContext) -> Vec<Span> {
         vec![Span::new(&self.value)]
     }
 
     fn cursor_offset_in_content(&self) -> usize {
         self.value
-            .chars()
+            .graphemes(true)
             .take(self.cursor_pos)
-            .map(|c| c.to_string().width())
+            .map(|g| g.width())
             .sum()
     }
 
@@ -218,3 +320,128 @@ impl Input for TextInput {
         self.delete_word_forward_impl();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+
+    fn input(value: &str) -> TextInput {
+        let mut input = TextInput::new("name", "Name");
+        input.set_value(value.to_string());
+        input
+    }
+
+    fn press(input: &mut TextInput, code: KeyCode) {
+        input.handle_key(code, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn arrows_step_over_whole_clusters() {
+        let mut family = input(&format!("a{FAMILY}b"));
+        assert_eq!(family.cursor_pos(), 3);
+        press(&mut family, KeyCode::Left);
+        press(&mut family, KeyCode::Left);
+        assert_eq!(family.cursor_pos(), 1);
+        press(&mut family, KeyCode::Right);
+        assert_eq!(family.cursor_pos(), 2);
+        assert_eq!(family.cursor_offset_in_content(), 1 + FAMILY.width());
+
+        let mut accent = input("cafe\u{301}!");
+        press(&mut accent, KeyCode::Left);
+        press(&mut accent, KeyCode::Left);
+        assert_eq!(accent.cursor_pos(), 3);
+        assert_eq!(accent.cursor_offset_in_content(), 3);
+        press(&mut accent, KeyCode::Right);
+        assert_eq!(accent.cursor_offset_in_content(), 4);
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_whole_clusters() {
+        let mut family = input(&format!("a{FAMILY}b"));
+        press(&mut family, KeyCode::Left);
+        press(&mut family, KeyCode::Backspace);
+        assert_eq!(family.value(), "ab");
+        assert_eq!(family.cursor_pos(), 1);
+
+        let mut accent = input("cafe\u{301}s");
+        press(&mut accent, KeyCode::Home);
+        press(&mut accent, KeyCode::Right);
+        press(&mut accent, KeyCode::Right);
+        press(&mut accent, KeyCode::Right);
+        press(&mut accent, KeyCode::Delete);
+        assert_eq!(accent.value(), "cafs");
+        assert_eq!(accent.cursor_pos(), 3);
+
+        press(&mut accent, KeyCode::End);
+        let revision = accent.revision();
+        press(&mut accent, KeyCode::Delete);
+        assert_eq!(accent.value(), "cafs");
+        assert_eq!(accent.revision(), revision);
+    }
+
+    #[test]
+    fn typing_a_combining_mark_joins_the_previous_cluster() {
+        let mut accent = input("cafe");
+        press(&mut accent, KeyCode::Char('\u{301}'));
+        assert_eq!(accent.value(), "cafe\u{301}");
+        assert_eq!(accent.cursor_pos(), 4);
+    }
+
+    #[test]
+    fn multi_line_pastes_are_stripped_to_one_line() {
+        let mut strip = input("> ");
+        assert_eq!(
+            strip.handle_paste("ssh-ed25519\r\nAAAA\tkey\n"),
+            KeyResult::Handled
+        );
+        assert_eq!(strip.value(), "> ssh-ed25519AAAAkey");
+        assert_eq!(strip.cursor_pos(), 20);
+    }
+
+    #[test]
+    fn multi_line_pastes_can_be_rejected() {
+        let mut reject = input("> ").with_paste_newlines(PasteNewlines::Reject);
+        let revision = reject.revision();
+        assert_eq!(reject.handle_paste("one\ntwo"), KeyResult::NotHandled);
+        assert_eq!(reject.value(), "> ");
+        assert_eq!(reject.revision(), revision);
+        assert_eq!(reject.error(), Some("Pasted text must be a single line"));
+
+        // A trailing line break alone is not a second line.
+        assert_eq!(reject.handle_paste("one\n"), KeyResult::Handled);
+        assert_eq!(reject.value(), "> one");
+        assert_eq!(reject.revision(), revision + 1);
+    }
+
+    #[test]
+    fn word_deletion_stops_at_whitespace_only() {
+        let mut words = input("user@example.com  next, word  ");
+        words.delete_word();
+        assert_eq!(words.value(), "user@example.com  next, ");
+        words.delete_word();
+        assert_eq!(words.value(), "user@example.com  ");
+        words.delete_word();
+        assert_eq!(words.value(), "");
+        assert_eq!(words.cursor_pos(), 0);
+
+        let mut forward = input("  foo.bar baz");
+        press(&mut forward, KeyCode::Home);
+        forward.delete_word_forward();
+        assert_eq!(forward.value(), " baz");
+        assert_eq!(forward.cursor_pos(), 0);
+    }
+
+    #[test]
+    fn cursor_offset_counts_wide_characters_twice() {
+        let mut cjk = input("a\u{65E5}\u{672C}b");
+        assert_eq!(cjk.cursor_offset_in_content(), 6);
+        press(&mut cjk, KeyCode::Left);
+        assert_eq!(cjk.cursor_offset_in_content(), 5);
+        press(&mut cjk, KeyCode::Left);
+        assert_eq!(cjk.cursor_offset_in_content(), 3);
+        press(&mut cjk, KeyCode::Left);
+        assert_eq!(cjk.cursor_offset_in_content(), 1);
+    }
+}
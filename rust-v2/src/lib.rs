@@ -8,18 +8,25 @@ pub use core::event;
 pub use core::event_emitter;
 pub use core::form_step;
 pub use core::input_manager;
+pub use core::line_mode;
 pub use core::view_state;
 
 pub use input::date_input;
+pub use input::password_input;
 pub use input::text_input;
 pub use input::validators;
 
 pub use terminal::terminal_event;
 
+pub use ui::export;
 pub use ui::frame;
 pub use ui::layout;
+pub use ui::markup;
 pub use ui::node;
+pub use ui::render_context;
 pub use ui::renderer;
+pub use ui::sgr;
 pub use ui::span;
 pub use ui::style;
+pub use ui::symbols;
 pub use ui::theme;
@@ -1,23 +1,68 @@
 use rustical::app::App;
-use rustical::terminal::Terminal;
+use rustical::form_step::FormStepExt;
+use rustical::input::NodeId;
+use rustical::line_mode::LineMode;
+use rustical::terminal::{Backend, Terminal, TerminalGuard, guard, tty};
 use rustical::terminal_event::TerminalEvent;
-use std::io;
+use rustical::theme::Theme;
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
 use std::time::Duration;
 
-fn main() {
-    if let Err(e) = run() {
-        eprintln!("Error: {}", e);
+fn main() -> ExitCode {
+    match run() {
+        Ok(Some(values)) => {
+            // The UI lives on the tty; stdout only carries the answers, as
+            // shell assignments that `eval "$(rustical)"` reads back intact.
+            let mut stdout = io::stdout().lock();
+            for (id, value) in values {
+                let _ = writeln!(stdout, "{}={}", id, shell_quote(&value));
+            }
+            ExitCode::SUCCESS
+        }
+        Ok(None) => ExitCode::from(130),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
     }
 }
 
-fn run() -> io::Result<()> {
+fn run() -> io::Result<Option<Vec<(NodeId, String)>>> {
+    // Read before touching the terminal, so a bad theme is reported plainly.
+    let theme = Theme::from_env()?;
+
+    if !tty::is_interactive() {
+        // No terminal to draw on: ask line by line, prompts on stderr.
+        let mut app = App::new();
+        let stdin = io::stdin();
+        let typed = stdin.is_terminal();
+        let mut line_mode = LineMode::new(stdin.lock(), io::stderr())
+            .with_answer_newline(!typed)
+            .with_hidden_secrets(typed)
+            .with_theme(theme);
+        return line_mode.run(&mut app.form.step).map(Some);
+    }
+
+    let mouse = std::env::args().skip(1).any(|arg| arg == "--mouse");
+
+    // Undoes the setup below on early returns, panics and fatal signals.
+    let _guard = TerminalGuard::new();
     let mut terminal = Terminal::new()?;
     terminal.enter_raw_mode()?;
     terminal.set_line_wrap(false)?;
     terminal.hide_cursor()?;
+    terminal.set_bracketed_paste(true)?;
+    if mouse {
+        terminal.set_mouse_capture(true)?;
+    }
 
-    let result = event_loop(&mut terminal);
+    let result = event_loop(&mut terminal, theme);
 
+    if mouse {
+        terminal.set_mouse_capture(false)?;
+    }
+    terminal.set_bracketed_paste(false)?;
     terminal.show_cursor()?;
     terminal.set_line_wrap(true)?;
     terminal.exit_raw_mode()?;
@@ -25,8 +70,8 @@ fn run() -> io::Result<()> {
     result
 }
 
-fn event_loop(terminal: &mut Terminal) -> io::Result<()> {
-    let mut app = App::new();
+fn event_loop(terminal: &mut Terminal, theme: Theme) -> io::Result<Option<Vec<(NodeId, String)>>> {
+    let mut app = App::new().with_theme(theme);
 
     let mut render_requested = true;
 
@@ -44,6 +89,14 @@ fn event_loop(terminal: &mut Terminal) -> io::Result<()> {
                         break;
                     }
                 }
+                TerminalEvent::Mouse(mouse_event) => {
+                    app.handle_mouse(mouse_event);
+                    render_requested = true;
+                }
+                TerminalEvent::Paste(text) => {
+                    app.handle_paste(text);
+                    render_requested = true;
+                }
                 TerminalEvent::Resize { .. } => {
                     render_requested = true;
                 }
@@ -54,6 +107,16 @@ fn event_loop(terminal: &mut Terminal) -> io::Result<()> {
             render_requested = true;
         }
 
+        guard::exit_on_signal();
+
+        if guard::take_stop_request() || app.suspend_requested {
+            app.suspend_requested = false;
+            app.renderer.move_to_end(terminal)?;
+            guard::suspend()?;
+            app.renderer.reset(terminal)?;
+            render_requested = true;
+        }
+
         if app.should_exit {
             break;
         }
@@ -67,6 +130,32 @@ fn event_loop(terminal: &mut Terminal) -> io::Result<()> {
     app.renderer.move_to_end(terminal)?;
     terminal.clear_from_cursor_down()?;
 
+    Ok(app.submitted.then(|| app.form.step.values()))
+}
 
-    Ok(())
+/// `value` as a single shell word: unchanged when it only holds characters
+/// the shell leaves alone, otherwise in single quotes.
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "@%+=:,./_-".contains(ch));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shell_quote;
+
+    #[test]
+    fn quotes_values_for_the_shell() {
+        assert_eq!(shell_quote("bob@example.com"), "bob@example.com");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b\nc=d"), "'a b\nc=d'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
 }
@@ -0,0 +1,50 @@
+use crate::frame::Line;
+use crate::terminal_event::TerminalEvent;
+use std::io;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pos {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Everything the renderer needs from a terminal.
+///
+/// `queue_*` calls only buffer output; nothing is guaranteed to reach the
+/// screen before `flush`.
+pub trait Backend {
+    fn size(&self) -> Size;
+    fn refresh_size(&mut self) -> io::Result<bool>;
+
+    fn cursor_position(&self) -> Pos;
+    fn refresh_cursor_position(&mut self) -> io::Result<()>;
+
+    fn poll(&self, timeout: Duration) -> io::Result<bool>;
+    fn read_event(&mut self) -> io::Result<TerminalEvent>;
+
+    fn queue_hide_cursor(&mut self) -> io::Result<()>;
+    fn queue_show_cursor(&mut self) -> io::Result<()>;
+    fn queue_move_cursor(&mut self, x: u16, y: u16) -> io::Result<()>;
+    fn queue_clear_line(&mut self) -> io::Result<()>;
+    fn queue_clear_until_newline(&mut self) -> io::Result<()>;
+    fn queue_clear_from_cursor_down(&mut self) -> io::Result<()>;
+    /// Moves the cursor one row down, scrolling the screen when it is
+    /// already on the last row.
+    fn queue_newline(&mut self) -> io::Result<()>;
+    /// Brackets a frame so that terminals supporting synchronized output
+    /// show it all at once. A no-op elsewhere.
+    fn queue_begin_synchronized_update(&mut self) -> io::Result<()>;
+    fn queue_end_synchronized_update(&mut self) -> io::Result<()>;
+    fn queue_enter_alternate_screen(&mut self) -> io::Result<()>;
+    fn queue_leave_alternate_screen(&mut self) -> io::Result<()>;
+
+    fn render_line(&mut self, line: &Line) -> io::Result<()>;
+    fn flush(&mut self) -> io::Result<()>;
+}
@@ -0,0 +1,258 @@
+use crate::terminal::tty;
+use crossterm::{cursor, event, execute, terminal};
+use std::io::{self, Write};
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, Ordering};
+
+const RAW_MODE: u8 = 1 << 0;
+const CURSOR_HIDDEN: u8 = 1 << 1;
+const LINE_WRAP_DISABLED: u8 = 1 << 2;
+const MOUSE_CAPTURE: u8 = 1 << 3;
+const BRACKETED_PASTE: u8 = 1 << 4;
+const ALTERNATE_SCREEN: u8 = 1 << 5;
+
+/// Terminal modes switched on by `Terminal` that have to be undone before
+/// the shell gets the terminal back.
+static ACTIVE: AtomicU8 = AtomicU8::new(0);
+/// Set by the signal thread and picked up by the event loop, which does the
+/// actual work between frames.
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+static TERMINATE_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static INSTALL: Once = Once::new();
+
+/// Puts the terminal back the way the shell left it, however the program
+/// ends: when dropped and when a panic unwinds or aborts. SIGTERM, SIGHUP,
+/// SIGQUIT and SIGTSTP are caught and left for the event loop to act on;
+/// see [`take_stop_request`] and [`exit_on_signal`].
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    pub fn new() -> Self {
+        INSTALL.call_once(|| {
+            install_panic_hook();
+            #[cfg(unix)]
+            install_signal_handlers();
+        });
+        Self { _private: () }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+impl Default for TerminalGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Undoes every mode that is still on. Safe to call more than once.
+pub fn restore() {
+    let active = take_active();
+    if active != 0 {
+        let _ = apply(active, false);
+    }
+}
+
+/// Stops the process like Ctrl+Z in a normal shell program would, with the
+/// terminal restored while it is stopped. Returns once the process is
+/// continued (`fg`), with the terminal modes set up again; the caller
+/// should then redraw from scratch.
+///
+/// In raw mode the terminal sends Ctrl+Z as a key instead of raising
+/// SIGTSTP, so the key binding ends up here, as does a SIGTSTP from outside
+/// once [`take_stop_request`] reports it.
+pub fn suspend() -> io::Result<()> {
+    let active = take_active();
+    apply(active, false)?;
+    #[cfg(unix)]
+    stop_process();
+    ACTIVE.fetch_or(active, Ordering::SeqCst);
+    apply(active, true)
+}
+
+/// Whether a SIGTSTP arrived (e.g. `kill -TSTP`) since the last call. The
+/// caller should then [`suspend`].
+pub fn take_stop_request() -> bool {
+    STOP_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
+/// Restores the terminal and ends the process the way the signal would
+/// have, if SIGTERM, SIGHUP or SIGQUIT arrived. Returns otherwise.
+pub fn exit_on_signal() {
+    let signal = TERMINATE_SIGNAL.load(Ordering::SeqCst);
+    if signal == 0 {
+        return;
+    }
+    restore();
+    #[cfg(unix)]
+    let _ = signal_hook::low_level::emulate_default_handler(signal);
+}
+
+pub(crate) fn set_raw_mode(on: bool) {
+    set(RAW_MODE, on);
+}
+
+pub(crate) fn set_cursor_hidden(on: bool) {
+    set(CURSOR_HIDDEN, on);
+}
+
+pub(crate) fn set_line_wrap_disabled(on: bool) {
+    set(LINE_WRAP_DISABLED, on);
+}
+
+pub(crate) fn set_mouse_capture(on: bool) {
+    set(MOUSE_CAPTURE, on);
+}
+
+pub(crate) fn set_bracketed_paste(on: bool) {
+    set(BRACKETED_PASTE, on);
+}
+
+pub(crate) fn set_alternate_screen(on: bool) {
+    set(ALTERNATE_SCREEN, on);
+}
+
+/// Clears the active modes and returns them, so that only one caller gets
+/// to undo each.
+fn take_active() -> u8 {
+    ACTIVE.swap(0, Ordering::SeqCst)
+}
+
+fn set(flag: u8, on: bool) {
+    if on {
+        ACTIVE.fetch_or(flag, Ordering::SeqCst);
+    } else {
+        ACTIVE.fetch_and(!flag, Ordering::SeqCst);
+    }
+}
+
+/// Switches the modes in `flags` on (`enable`) or back off, in the reverse
+/// order of how they stack up.
+fn apply(flags: u8, enable: bool) -> io::Result<()> {
+    let mut out = tty::open_output();
+    let has = |flag: u8| flags & flag != 0;
+
+    if enable {
+        if has(RAW_MODE) {
+            terminal::enable_raw_mode()?;
+        }
+        if has(ALTERNATE_SCREEN) {
+            execute!(out, terminal::EnterAlternateScreen)?;
+        }
+        if has(LINE_WRAP_DISABLED) {
+            execute!(out, terminal::DisableLineWrap)?;
+        }
+        if has(CURSOR_HIDDEN) {
+            execute!(out, cursor::Hide)?;
+        }
+        if has(MOUSE_CAPTURE) {
+            execute!(out, event::EnableMouseCapture)?;
+        }
+        if has(BRACKETED_PASTE) {
+            execute!(out, event::EnableBracketedPaste)?;
+        }
+    } else {
+        if has(BRACKETED_PASTE) {
+            execute!(out, event::DisableBracketedPaste)?;
+        }
+        if has(MOUSE_CAPTURE) {
+            execute!(out, event::DisableMouseCapture)?;
+        }
+        if has(CURSOR_HIDDEN) {
+            execute!(out, cursor::Show)?;
+        }
+        if has(LINE_WRAP_DISABLED) {
+            execute!(out, terminal::EnableLineWrap)?;
+        }
+        if has(ALTERNATE_SCREEN) {
+            execute!(out, terminal::LeaveAlternateScreen)?;
+        }
+        if has(RAW_MODE) {
+            terminal::disable_raw_mode()?;
+        }
+    }
+    out.flush()
+}
+
+fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Restore first so the panic message is printed on a sane terminal.
+        restore();
+        previous(info);
+    }));
+}
+
+#[cfg(unix)]
+fn install_signal_handlers() {
+    use signal_hook::consts::{SIGHUP, SIGQUIT, SIGTERM, SIGTSTP};
+    use signal_hook::iterator::Signals;
+
+    // SIGINT stays with its default: in raw mode Ctrl+C arrives as a key.
+    let Ok(mut signals) = Signals::new([SIGTERM, SIGHUP, SIGQUIT, SIGTSTP]) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            if signal == SIGTSTP {
+                STOP_REQUESTED.store(true, Ordering::SeqCst);
+            } else {
+                TERMINATE_SIGNAL.store(signal, Ordering::SeqCst);
+            }
+        }
+    });
+}
+
+/// Stops the process with the default SIGTSTP action, so the shell sees a
+/// normal job stop. Blocks until SIGCONT, then puts our handler back.
+///
+/// The handler has to go first: while it is installed, a raised SIGTSTP
+/// only sets the stop request again and the process keeps running. Only
+/// the default action actually stops it.
+#[cfg(unix)]
+fn stop_process() {
+    unsafe {
+        let mut default: libc::sigaction = std::mem::zeroed();
+        default.sa_sigaction = libc::SIG_DFL;
+        let mut previous: libc::sigaction = std::mem::zeroed();
+        if libc::sigaction(libc::SIGTSTP, &default, &mut previous) != 0 {
+            return;
+        }
+        libc::raise(libc::SIGTSTP);
+        libc::sigaction(libc::SIGTSTP, &previous, std::ptr::null_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_active_mode_is_undone_once() {
+        set_raw_mode(true);
+        set_mouse_capture(true);
+        set_bracketed_paste(true);
+        set_bracketed_paste(false);
+        assert_eq!(ACTIVE.load(Ordering::SeqCst), RAW_MODE | MOUSE_CAPTURE);
+
+        // What `restore` and `suspend` undo is cleared, so a second restore
+        // finds nothing left to do.
+        assert_eq!(take_active(), RAW_MODE | MOUSE_CAPTURE);
+        assert_eq!(take_active(), 0);
+        restore();
+        assert_eq!(ACTIVE.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn a_stop_request_is_taken_once() {
+        STOP_REQUESTED.store(true, Ordering::SeqCst);
+        assert!(take_stop_request());
+        assert!(!take_stop_request());
+    }
+}
@@ -1,4 +1,12 @@
+pub mod backend;
+pub mod guard;
+#[allow(clippy::module_inception)]
 pub mod terminal;
 pub mod terminal_event;
+pub mod test_backend;
+pub mod tty;
 
+pub use backend::{Backend, Pos, Size};
+pub use guard::TerminalGuard;
 pub use terminal::Terminal;
+pub use test_backend::TestBackend;
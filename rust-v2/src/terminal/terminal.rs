@@ -1,93 +1,193 @@
 use crate::frame::{Frame, Line};
-use crate::style::Color;
+use crate::sgr::SgrState;
+use crate::style::ColorSupport;
+use crate::terminal::backend::{Backend, Pos, Size};
+use crate::terminal::{guard, tty};
 use crate::terminal_event::TerminalEvent;
-use crossterm::event::{poll, read, Event};
-use crossterm::style::{Attribute, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::event::{self, Event, poll, read};
 use crossterm::{cursor, execute, queue, terminal};
-use std::io::{self, Stdout, Write};
+use std::io::{self, Write};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Copy)]
-pub struct Size {
-    pub width: u16,
-    pub height: u16,
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct Pos {
-    pub x: u16,
-    pub y: u16,
-}
-
+/// The real terminal, driven through crossterm.
+///
+/// Output goes to `/dev/tty` (or stderr when there is no controlling
+/// terminal), never to stdout, so the program's answers can be captured with
+/// shell command substitution.
 pub struct Terminal {
-    stdout: Stdout,
+    out: Box<dyn Write + Send>,
     size: Size,
     cursor: Pos,
+    color_support: ColorSupport,
+    synchronized_output: bool,
+    sgr: SgrState,
 }
 
 impl Terminal {
     pub fn new() -> io::Result<Self> {
-        let stdout = io::stdout();
+        let mut out = tty::open_output();
         let (width, height) = terminal::size()?;
-        let (x, y) = cursor::position()?;
+        let (x, y) = tty::cursor_position(&mut out)?;
+        let synchronized_output = tty::supports_synchronized_output(&mut out);
         Ok(Self {
-            stdout,
+            out,
             size: Size { width, height },
             cursor: Pos { x, y },
+            color_support: ColorSupport::detect(),
+            synchronized_output,
+            sgr: SgrState::new(),
         })
     }
 
-    pub fn writer_mut(&mut self) -> &mut Stdout {
-        &mut self.stdout
+    pub fn color_support(&self) -> ColorSupport {
+        self.color_support
+    }
+
+    pub fn set_color_support(&mut self, support: ColorSupport) {
+        self.color_support = support;
+    }
+
+    pub fn synchronized_output(&self) -> bool {
+        self.synchronized_output
+    }
+
+    pub fn set_synchronized_output(&mut self, enabled: bool) {
+        self.synchronized_output = enabled;
+    }
+
+    pub fn writer_mut(&mut self) -> &mut dyn Write {
+        &mut self.out
     }
 
     pub fn enter_raw_mode(&mut self) -> io::Result<()> {
-        terminal::enable_raw_mode()
+        terminal::enable_raw_mode()?;
+        guard::set_raw_mode(true);
+        Ok(())
     }
 
     pub fn exit_raw_mode(&mut self) -> io::Result<()> {
-        terminal::disable_raw_mode()
+        terminal::disable_raw_mode()?;
+        guard::set_raw_mode(false);
+        Ok(())
     }
 
     pub fn set_line_wrap(&mut self, enabled: bool) -> io::Result<()> {
         if enabled {
-            execute!(self.stdout, terminal::EnableLineWrap)?;
+            execute!(self.out, terminal::EnableLineWrap)?;
         } else {
-            execute!(self.stdout, terminal::DisableLineWrap)?;
+            execute!(self.out, terminal::DisableLineWrap)?;
         }
+        guard::set_line_wrap_disabled(!enabled);
         Ok(())
     }
 
-    pub fn size(&self) -> Size {
-        self.size
+    /// Mouse reporting is opt-in: while it is on, the terminal's own text
+    /// selection needs Shift held down.
+    pub fn set_mouse_capture(&mut self, enabled: bool) -> io::Result<()> {
+        if enabled {
+            execute!(self.out, event::EnableMouseCapture)?;
+        } else {
+            execute!(self.out, event::DisableMouseCapture)?;
+        }
+        guard::set_mouse_capture(enabled);
+        Ok(())
     }
 
-    pub fn cursor_position(&self) -> Pos {
-        self.cursor
+    /// With bracketed paste on, pasted text arrives as a single
+    /// `TerminalEvent::Paste` instead of one key event per character.
+    pub fn set_bracketed_paste(&mut self, enabled: bool) -> io::Result<()> {
+        if enabled {
+            execute!(self.out, event::EnableBracketedPaste)?;
+        } else {
+            execute!(self.out, event::DisableBracketedPaste)?;
+        }
+        guard::set_bracketed_paste(enabled);
+        Ok(())
+    }
+
+    pub fn hide_cursor(&mut self) -> io::Result<()> {
+        execute!(self.out, cursor::Hide)?;
+        guard::set_cursor_hidden(true);
+        Ok(())
+    }
+
+    pub fn show_cursor(&mut self) -> io::Result<()> {
+        execute!(self.out, cursor::Show)?;
+        guard::set_cursor_hidden(false);
+        Ok(())
     }
 
-    pub fn refresh_size(&mut self) -> io::Result<bool> {
+    pub fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        execute!(self.out, cursor::MoveTo(x, y))?;
+        self.cursor = Pos { x, y };
+        Ok(())
+    }
+
+    pub fn clear_line(&mut self) -> io::Result<()> {
+        execute!(self.out, terminal::Clear(terminal::ClearType::CurrentLine))?;
+        Ok(())
+    }
+
+    pub fn clear_from_cursor_down(&mut self) -> io::Result<()> {
+        execute!(
+            self.out,
+            terminal::Clear(terminal::ClearType::FromCursorDown)
+        )?;
+        Ok(())
+    }
+
+    pub fn render_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        for (i, line) in frame.lines().iter().enumerate() {
+            if i > 0 {
+                writeln!(self.out)?;
+            }
+            self.render_line(line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Backend for Terminal {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn refresh_size(&mut self) -> io::Result<bool> {
         let (width, height) = terminal::size()?;
         let changed = self.size.width != width || self.size.height != height;
         self.size = Size { width, height };
         Ok(changed)
     }
 
-    pub fn refresh_cursor_position(&mut self) -> io::Result<()> {
-        let (x, y) = cursor::position()?;
+    fn cursor_position(&self) -> Pos {
+        self.cursor
+    }
+
+    fn refresh_cursor_position(&mut self) -> io::Result<()> {
+        // The answer has to reflect everything queued so far.
+        self.out.flush()?;
+        let (x, y) = tty::cursor_position(&mut self.out)?;
         self.cursor = Pos { x, y };
         Ok(())
     }
 
-    pub fn poll(&self, timeout: Duration) -> io::Result<bool> {
+    fn poll(&self, timeout: Duration) -> io::Result<bool> {
+        if tty::has_typed_ahead() {
+            return Ok(true);
+        }
         poll(timeout)
     }
 
-    pub fn read_event(&mut self) -> io::Result<TerminalEvent> {
+    fn read_event(&mut self) -> io::Result<TerminalEvent> {
+        if let Some(key) = tty::next_typed_ahead() {
+            return Ok(TerminalEvent::Key(key));
+        }
         loop {
             let event = read()?;
             match event {
                 Event::Key(key) => return Ok(TerminalEvent::Key(key)),
+                Event::Mouse(mouse) => return Ok(TerminalEvent::Mouse(mouse)),
+                Event::Paste(text) => return Ok(TerminalEvent::Paste(text)),
                 Event::Resize(width, height) => {
                     self.size = Size { width, height };
                     return Ok(TerminalEvent::Resize { width, height });
@@ -97,113 +197,151 @@ impl Terminal {
         }
     }
 
-    pub fn hide_cursor(&mut self) -> io::Result<()> {
-        execute!(self.stdout, cursor::Hide)?;
+    fn queue_hide_cursor(&mut self) -> io::Result<()> {
+        queue!(self.out, cursor::Hide)?;
+        guard::set_cursor_hidden(true);
         Ok(())
     }
 
-    pub fn show_cursor(&mut self) -> io::Result<()> {
-        execute!(self.stdout, cursor::Show)?;
+    fn queue_show_cursor(&mut self) -> io::Result<()> {
+        queue!(self.out, cursor::Show)?;
+        guard::set_cursor_hidden(false);
         Ok(())
     }
 
-    pub fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
-        execute!(self.stdout, cursor::MoveTo(x, y))?;
+    fn queue_move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        queue!(self.out, cursor::MoveTo(x, y))?;
         self.cursor = Pos { x, y };
         Ok(())
     }
 
-    pub fn clear_line(&mut self) -> io::Result<()> {
-        execute!(self.stdout, terminal::Clear(terminal::ClearType::CurrentLine))?;
+    fn queue_clear_line(&mut self) -> io::Result<()> {
+        queue!(self.out, terminal::Clear(terminal::ClearType::CurrentLine))?;
         Ok(())
     }
 
-    pub fn clear_from_cursor_down(&mut self) -> io::Result<()> {
-        execute!(self.stdout, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+    fn queue_clear_until_newline(&mut self) -> io::Result<()> {
+        queue!(self.out, terminal::Clear(terminal::ClearType::UntilNewLine))?;
         Ok(())
     }
 
-    pub fn queue_hide_cursor(&mut self) -> io::Result<()> {
-        queue!(self.stdout, cursor::Hide)?;
+    fn queue_clear_from_cursor_down(&mut self) -> io::Result<()> {
+        queue!(
+            self.out,
+            terminal::Clear(terminal::ClearType::FromCursorDown)
+        )?;
         Ok(())
     }
 
-    pub fn queue_show_cursor(&mut self) -> io::Result<()> {
-        queue!(self.stdout, cursor::Show)?;
+    fn queue_newline(&mut self) -> io::Result<()> {
+        writeln!(self.out)?;
+        self.cursor.y = self
+            .cursor
+            .y
+            .saturating_add(1)
+            .min(self.size.height.saturating_sub(1));
         Ok(())
     }
 
-    pub fn queue_move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
-        queue!(self.stdout, cursor::MoveTo(x, y))?;
-        self.cursor = Pos { x, y };
+    fn queue_begin_synchronized_update(&mut self) -> io::Result<()> {
+        if self.synchronized_output {
+            queue!(self.out, terminal::BeginSynchronizedUpdate)?;
+        }
+        Ok(())
+    }
+
+    fn queue_end_synchronized_update(&mut self) -> io::Result<()> {
+        if self.synchronized_output {
+            queue!(self.out, terminal::EndSynchronizedUpdate)?;
+        }
         Ok(())
     }
 
-    pub fn queue_clear_line(&mut self) -> io::Result<()> {
-        queue!(self.stdout, terminal::Clear(terminal::ClearType::CurrentLine))?;
+    fn queue_enter_alternate_screen(&mut self) -> io::Result<()> {
+        queue!(self.out, terminal::EnterAlternateScreen)?;
+        guard::set_alternate_screen(true);
         Ok(())
     }
 
-    pub fn render_line(&mut self, line: &Line) -> io::Result<()> {
+    fn queue_leave_alternate_screen(&mut self) -> io::Result<()> {
+        queue!(self.out, terminal::LeaveAlternateScreen)?;
+        guard::set_alternate_screen(false);
+        Ok(())
+    }
+
+    fn render_line(&mut self, line: &Line) -> io::Result<()> {
         for span in line.spans() {
-            let has_style = span.style().color().is_some()
-                || span.style().background().is_some()
-                || span.style().bold()
-                || span.style().italic()
-                || span.style().underline();
-
-            if let Some(fg) = span.style().color() {
-                write!(self.stdout, "{}", SetForegroundColor(map_color(fg)))?;
-            }
-            if let Some(bg) = span.style().background() {
-                write!(self.stdout, "{}", SetBackgroundColor(map_color(bg)))?;
-            }
+            let style = span.style().downsample(self.color_support);
+            self.sgr.transition(&mut self.out, &style)?;
+            write!(self.out, "{}", span.text())?;
+        }
+        // Leave the line with default attributes so clears and the next
+        // cursor move don't pick up the last span's colors.
+        self.sgr.reset(&mut self.out)
+    }
 
-            if span.style().bold() {
-                write!(self.stdout, "{}", SetAttribute(Attribute::Bold))?;
-            }
-            if span.style().italic() {
-                write!(self.stdout, "{}", SetAttribute(Attribute::Italic))?;
-            }
-            if span.style().underline() {
-                write!(self.stdout, "{}", SetAttribute(Attribute::Underlined))?;
-            }
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
 
-            write!(self.stdout, "{}", span.text())?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+    use std::sync::{Arc, Mutex};
 
-            if has_style {
-                write!(self.stdout, "{}", SetAttribute(Attribute::Reset))?;
-                write!(self.stdout, "{}", ResetColor)?;
-            }
+    /// Records every write that reaches the terminal.
+    #[derive(Clone, Default)]
+    struct Writes(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().push(buf.to_vec());
+            Ok(buf.len())
         }
-        Ok(())
-    }
 
-    pub fn render_frame(&mut self, frame: &Frame) -> io::Result<()> {
-        for (i, line) in frame.lines().iter().enumerate() {
-            if i > 0 {
-                writeln!(self.stdout)?;
-            }
-            self.render_line(line)?;
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
         }
-        Ok(())
     }
 
-    pub fn flush(&mut self) -> io::Result<()> {
-        self.stdout.flush()
+    /// Draws a one-line frame the way the renderer does and returns the
+    /// writes it took, with the probe answered by `reply`.
+    fn draw(reply: &[u8]) -> Vec<String> {
+        let writes = Writes::default();
+        let mut terminal = Terminal {
+            out: Box::new(tty::FrameWriter::new(writes.clone())),
+            size: Size {
+                width: 20,
+                height: 5,
+            },
+            cursor: Pos { x: 0, y: 0 },
+            color_support: ColorSupport::None,
+            synchronized_output: tty::reports_synchronized_output(reply),
+            sgr: SgrState::new(),
+        };
+        let mut line = Line::new();
+        line.push(Span::new("hello"));
+
+        terminal.queue_begin_synchronized_update().unwrap();
+        terminal.queue_move_cursor(0, 1).unwrap();
+        terminal.render_line(&line).unwrap();
+        terminal.queue_end_synchronized_update().unwrap();
+        terminal.flush().unwrap();
+        let writes = writes.0.lock().unwrap();
+        writes
+            .iter()
+            .map(|write| String::from_utf8_lossy(write).into_owned())
+            .collect()
     }
-}
 
-fn map_color(color: Color) -> crossterm::style::Color {
-    match color {
-        Color::Black => crossterm::style::Color::Black,
-        Color::DarkGrey => crossterm::style::Color::DarkGrey,
-        Color::Red => crossterm::style::Color::Red,
-        Color::Green => crossterm::style::Color::Green,
-        Color::Yellow => crossterm::style::Color::Yellow,
-        Color::Blue => crossterm::style::Color::Blue,
-        Color::Magenta => crossterm::style::Color::Magenta,
-        Color::Cyan => crossterm::style::Color::Cyan,
-        Color::White => crossterm::style::Color::White,
+    #[test]
+    fn update_markers_follow_the_probe() {
+        let writes = draw(b"\x1B[?2026;2$y\x1B[?62;22c");
+        assert_eq!(writes, vec!["\x1B[?2026h\x1B[2;1Hhello\x1B[?2026l"]);
+
+        let writes = draw(b"\x1B[?62;22c");
+        assert_eq!(writes, vec!["\x1B[2;1Hhello"]);
     }
 }
@@ -1,9 +1,16 @@
-use crossterm::event::{Event, KeyEvent};
+use crossterm::event::{Event, KeyEvent, MouseEvent};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum TerminalEvent {
     Key(KeyEvent),
-    Resize { width: u16, height: u16 },
+    /// Only delivered while mouse capture is on.
+    Mouse(MouseEvent),
+    /// Text pasted while bracketed paste is on, delivered in one piece.
+    Paste(String),
+    Resize {
+        width: u16,
+        height: u16,
+    },
 }
 
 impl TryFrom<Event> for TerminalEvent {
@@ -12,6 +19,8 @@ impl TryFrom<Event> for TerminalEvent {
     fn try_from(event: Event) -> Result<Self, Self::Error> {
         match event {
             Event::Key(key) => Ok(TerminalEvent::Key(key)),
+            Event::Mouse(mouse) => Ok(TerminalEvent::Mouse(mouse)),
+            Event::Paste(text) => Ok(TerminalEvent::Paste(text)),
             Event::Resize(width, height) => Ok(TerminalEvent::Resize { width, height }),
             _ => Err(()),
         }
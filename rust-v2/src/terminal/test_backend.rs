@@ -0,0 +1,373 @@
+use crate::frame::Line;
+use crate::style::Style;
+use crate::terminal::backend::{Backend, Pos, Size};
+use crate::terminal_event::TerminalEvent;
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    symbol: String,
+    style: Style,
+}
+
+impl Cell {
+    fn blank() -> Self {
+        Self {
+            symbol: " ".to_string(),
+            style: Style::default(),
+        }
+    }
+
+    /// Continuation of a wide character drawn in the cell to the left.
+    fn continuation(style: Style) -> Self {
+        Self {
+            symbol: String::new(),
+            style,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+}
+
+/// In-memory terminal backed by a grid of cells.
+///
+/// Behaves like a terminal with line wrap disabled: text past the right edge
+/// is dropped, and a newline on the last row scrolls the grid up.
+pub struct TestBackend {
+    size: Size,
+    cells: Vec<Cell>,
+    cursor: Pos,
+    cursor_visible: bool,
+    events: VecDeque<TerminalEvent>,
+    main_screen: Option<(Vec<Cell>, Pos)>,
+}
+
+impl TestBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            size: Size { width, height },
+            cells: vec![Cell::blank(); width as usize * height as usize],
+            cursor: Pos { x: 0, y: 0 },
+            cursor_visible: true,
+            events: VecDeque::new(),
+            main_screen: None,
+        }
+    }
+
+    pub fn with_cursor(mut self, x: u16, y: u16) -> Self {
+        self.cursor = clamp_pos(Pos { x, y }, self.size);
+        self
+    }
+
+    pub fn push_event(&mut self, event: TerminalEvent) {
+        self.events.push_back(event);
+    }
+
+    /// Resizes the grid, keeping the top-left contents, and queues the
+    /// matching resize event.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let new_size = Size { width, height };
+        self.cells = resize_grid(&self.cells, self.size, new_size);
+        if let Some((cells, cursor)) = &mut self.main_screen {
+            *cells = resize_grid(cells, self.size, new_size);
+            *cursor = clamp_pos(*cursor, new_size);
+        }
+        self.size = new_size;
+        self.cursor = clamp_pos(self.cursor, new_size);
+        self.events
+            .push_back(TerminalEvent::Resize { width, height });
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x < self.size.width && y < self.size.height {
+            Some(&self.cells[self.index(x, y)])
+        } else {
+            None
+        }
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    pub fn is_alternate_screen(&self) -> bool {
+        self.main_screen.is_some()
+    }
+
+    /// Text of a single row with trailing blanks removed.
+    pub fn row_text(&self, y: u16) -> String {
+        let start = self.index(0, y);
+        let end = start + self.size.width as usize;
+        let text: String = self.cells[start..end]
+            .iter()
+            .map(|c| c.symbol.as_str())
+            .collect();
+        text.trim_end().to_string()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.size.height).map(|y| self.row_text(y)).collect()
+    }
+
+    /// Whole screen as text, one row per line, without trailing empty rows.
+    pub fn contents(&self) -> String {
+        let mut lines = self.lines();
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.size.width as usize + x as usize
+    }
+
+    fn clear_row(&mut self, y: u16) {
+        let start = self.index(0, y);
+        let end = start + self.size.width as usize;
+        self.cells[start..end].fill(Cell::blank());
+    }
+
+    fn scroll_up(&mut self) {
+        let width = self.size.width as usize;
+        self.cells.drain(..width);
+        self.cells.extend(std::iter::repeat_n(Cell::blank(), width));
+    }
+
+    fn put_grapheme(&mut self, grapheme: &str, style: &Style) {
+        let width = grapheme.width() as u16;
+        let Pos { x, y } = self.cursor;
+        if width == 0 {
+            if x > 0 {
+                let idx = self.index(x - 1, y);
+                self.cells[idx].symbol.push_str(grapheme);
+            }
+            return;
+        }
+        if x + width > self.size.width {
+            return;
+        }
+
+        let idx = self.index(x, y);
+        self.cells[idx] = Cell {
+            symbol: grapheme.to_string(),
+            style: style.clone(),
+        };
+        for offset in 1..width {
+            let idx = self.index(x + offset, y);
+            self.cells[idx] = Cell::continuation(style.clone());
+        }
+        self.cursor.x = x + width;
+    }
+}
+
+fn resize_grid(cells: &[Cell], old: Size, new: Size) -> Vec<Cell> {
+    let mut resized = vec![Cell::blank(); new.width as usize * new.height as usize];
+    for y in 0..new.height.min(old.height) as usize {
+        for x in 0..new.width.min(old.width) as usize {
+            resized[y * new.width as usize + x] = cells[y * old.width as usize + x].clone();
+        }
+    }
+    resized
+}
+
+fn clamp_pos(pos: Pos, size: Size) -> Pos {
+    Pos {
+        x: pos.x.min(size.width.saturating_sub(1)),
+        y: pos.y.min(size.height.saturating_sub(1)),
+    }
+}
+
+impl Backend for TestBackend {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn refresh_size(&mut self) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    fn cursor_position(&self) -> Pos {
+        self.cursor
+    }
+
+    fn refresh_cursor_position(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn poll(&self, _timeout: Duration) -> io::Result<bool> {
+        Ok(!self.events.is_empty())
+    }
+
+    fn read_event(&mut self) -> io::Result<TerminalEvent> {
+        self.events
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::WouldBlock, "no queued events"))
+    }
+
+    fn queue_hide_cursor(&mut self) -> io::Result<()> {
+        self.cursor_visible = false;
+        Ok(())
+    }
+
+    fn queue_show_cursor(&mut self) -> io::Result<()> {
+        self.cursor_visible = true;
+        Ok(())
+    }
+
+    fn queue_move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.cursor = clamp_pos(Pos { x, y }, self.size);
+        Ok(())
+    }
+
+    fn queue_clear_line(&mut self) -> io::Result<()> {
+        self.clear_row(self.cursor.y);
+        Ok(())
+    }
+
+    fn queue_clear_until_newline(&mut self) -> io::Result<()> {
+        let Pos { x, y } = self.cursor;
+        let start = self.index(x, y);
+        let end = self.index(0, y) + self.size.width as usize;
+        self.cells[start..end].fill(Cell::blank());
+        Ok(())
+    }
+
+    fn queue_clear_from_cursor_down(&mut self) -> io::Result<()> {
+        let Pos { x, y } = self.cursor;
+        let start = self.index(x, y);
+        self.cells[start..].fill(Cell::blank());
+        Ok(())
+    }
+
+    fn queue_newline(&mut self) -> io::Result<()> {
+        if self.cursor.y + 1 >= self.size.height {
+            self.scroll_up();
+        } else {
+            self.cursor.y += 1;
+        }
+        Ok(())
+    }
+
+    fn queue_begin_synchronized_update(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn queue_end_synchronized_update(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn queue_enter_alternate_screen(&mut self) -> io::Result<()> {
+        if self.main_screen.is_none() {
+            let blank = vec![Cell::blank(); self.cells.len()];
+            let cells = std::mem::replace(&mut self.cells, blank);
+            self.main_screen = Some((cells, self.cursor));
+        }
+        Ok(())
+    }
+
+    fn queue_leave_alternate_screen(&mut self) -> io::Result<()> {
+        if let Some((cells, cursor)) = self.main_screen.take() {
+            self.cells = cells;
+            self.cursor = cursor;
+        }
+        Ok(())
+    }
+
+    fn render_line(&mut self, line: &Line) -> io::Result<()> {
+        for span in line.spans() {
+            for grapheme in span.text().graphemes(true) {
+                self.put_grapheme(grapheme, span.style());
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::form_step::FormStep;
+    use crate::input::Input;
+    use crate::node::Node;
+    use crate::renderer::Renderer;
+    use crate::style::Color;
+    use crate::text_input::TextInput;
+    use crate::theme::Theme;
+    use crate::view_state::ViewState;
+
+    fn step() -> FormStep {
+        let mut name = TextInput::new("name", "Name");
+        name.set_value("bob".to_string());
+        name.set_focused(true);
+        FormStep {
+            prompt: "Sign in".to_string(),
+            hint: Some("Enter to submit".to_string()),
+            nodes: vec![
+                Node::input(name),
+                Node::input(TextInput::new("email", "Email")),
+            ],
+            render_mode: None,
+        }
+    }
+
+    #[test]
+    fn renders_a_step_into_cells() {
+        let mut backend = TestBackend::new(30, 8);
+        let theme = Theme::default_theme();
+        Renderer::new()
+            .render(&step(), &ViewState::new(), &theme, &mut backend)
+            .unwrap();
+
+        assert_eq!(
+            backend.contents(),
+            "Sign in\nName:  [bob]\nEmail:\nEnter to submit"
+        );
+        assert!(backend.cell(0, 0).unwrap().style().bold());
+        assert_eq!(backend.cell(8, 1).unwrap().symbol(), "b");
+        assert_eq!(
+            backend.cell(0, 3).unwrap().style().color(),
+            Some(Color::DarkGrey)
+        );
+        // The cursor sits after the focused value.
+        assert!(backend.is_cursor_visible());
+        assert_eq!(backend.cursor_position(), Pos { x: 11, y: 1 });
+    }
+
+    #[test]
+    fn redraws_only_what_changed() {
+        let mut backend = TestBackend::new(30, 8);
+        let theme = Theme::default_theme();
+        let mut renderer = Renderer::new();
+        let mut step = step();
+        renderer
+            .render(&step, &ViewState::new(), &theme, &mut backend)
+            .unwrap();
+
+        step.nodes[0]
+            .as_input_mut()
+            .unwrap()
+            .set_value("alice".to_string());
+        renderer
+            .render(&step, &ViewState::new(), &theme, &mut backend)
+            .unwrap();
+
+        assert_eq!(backend.row_text(1), "Name:  [alice]");
+        assert_eq!(backend.row_text(2), "Email:");
+        assert_eq!(backend.cursor_position(), Pos { x: 13, y: 1 });
+    }
+}
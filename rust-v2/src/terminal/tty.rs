@@ -0,0 +1,504 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::{cursor, terminal};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+const ESC: u8 = 0x1B;
+
+/// Keys typed while a query was waiting for its reply, in order. They were
+/// read from `/dev/tty` before crossterm could see them.
+static TYPED_AHEAD: Mutex<VecDeque<KeyEvent>> = Mutex::new(VecDeque::new());
+
+/// Where the UI is drawn: the controlling terminal when there is one, so
+/// that stdout stays free for results, otherwise stderr.
+pub fn open_output() -> Box<dyn Write + Send> {
+    match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(tty) => Box::new(FrameWriter::new(tty)),
+        Err(_) => Box::new(FrameWriter::new(io::stderr())),
+    }
+}
+
+/// Keeps everything written until `flush` and then passes it on in a single
+/// write, so the terminal never receives half a frame.
+pub struct FrameWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buffer: Vec::with_capacity(16 * 1024),
+        }
+    }
+}
+
+impl<W: Write> Write for FrameWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let result = self.inner.write_all(&self.buffer);
+            self.buffer.clear();
+            result?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// Whether the interactive UI can run: keys come from a terminal and there
+/// is a controlling terminal to draw on. Piping stdout alone is fine, the
+/// UI goes to `/dev/tty` then.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal()
+        && OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .is_ok()
+}
+
+/// Turns off echo on stdin until dropped, so that a secret typed in line
+/// mode does not show up. Enter still echoes and ends the prompt line.
+pub struct HiddenStdinEcho {
+    #[cfg(unix)]
+    saved: libc::termios,
+}
+
+impl HiddenStdinEcho {
+    #[cfg(unix)]
+    pub fn new() -> io::Result<Self> {
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut saved) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut hidden = saved;
+        hidden.c_lflag &= !libc::ECHO;
+        hidden.c_lflag |= libc::ECHONL;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &hidden) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { saved })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot hide what is typed on stdin",
+        ))
+    }
+}
+
+impl Drop for HiddenStdinEcho {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.saved);
+        }
+    }
+}
+
+/// Asks the terminal for the cursor position, writing the request to `out`.
+///
+/// crossterm's `cursor::position` always writes to stdout, which would end up
+/// in the captured output of `$(...)`, so it is only used when stdout is the
+/// terminal itself.
+pub fn cursor_position(out: &mut dyn Write) -> io::Result<(u16, u16)> {
+    if io::stdout().is_terminal() {
+        return cursor::position();
+    }
+
+    let reply = query(out, b"\x1B[6n", |reply| reply.ends_with(b"R"))?;
+    parse_cursor_position(&reply)
+        .ok_or_else(|| io::Error::other("could not read the cursor position"))
+}
+
+/// Asks whether the terminal implements synchronized updates (DEC private
+/// mode 2026). The DECRQM request is followed by a primary device attributes
+/// request, which every terminal answers, so terminals that ignore DECRQM
+/// don't keep us waiting for the timeout.
+pub fn supports_synchronized_output(out: &mut dyn Write) -> bool {
+    let done = |reply: &[u8]| reply.ends_with(b"c") && contains(reply, b"\x1B[?");
+    query(out, b"\x1B[?2026$p\x1B[c", done).is_ok_and(|reply| reports_synchronized_output(&reply))
+}
+
+/// Whether the replies to the probe above report mode 2026 as set (1) or
+/// reset (2). Anything else means the terminal does not know the mode.
+pub(crate) fn reports_synchronized_output(reply: &[u8]) -> bool {
+    contains(reply, b"\x1B[?2026;1$y") || contains(reply, b"\x1B[?2026;2$y")
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Whether keys typed during a query are waiting to be read.
+pub fn has_typed_ahead() -> bool {
+    TYPED_AHEAD.lock().is_ok_and(|keys| !keys.is_empty())
+}
+
+/// The oldest key typed during a query, if any.
+pub fn next_typed_ahead() -> Option<KeyEvent> {
+    TYPED_AHEAD.lock().ok()?.pop_front()
+}
+
+/// Writes `request` to `out` and collects the terminal's reply from
+/// `/dev/tty` until `done` accepts it or the timeout expires. `done` only
+/// sees the reply sequences; whatever the user types meanwhile is kept for
+/// [`next_typed_ahead`].
+pub fn query(
+    out: &mut dyn Write,
+    request: &[u8],
+    done: impl Fn(&[u8]) -> bool,
+) -> io::Result<Vec<u8>> {
+    let mut tty = File::open("/dev/tty")?;
+    let was_raw = terminal::is_raw_mode_enabled()?;
+    if !was_raw {
+        terminal::enable_raw_mode()?;
+    }
+
+    let result = (|| {
+        out.write_all(request)?;
+        out.flush()?;
+        read_reply(&mut tty, done)
+    })();
+
+    if !was_raw {
+        terminal::disable_raw_mode()?;
+    }
+    result
+}
+
+#[cfg(unix)]
+fn read_reply(tty: &mut File, done: impl Fn(&[u8]) -> bool) -> io::Result<Vec<u8>> {
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut received = Vec::new();
+    let mut reply = Vec::new();
+    let mut typed = Vec::new();
+    let mut chunk = [0u8; 256];
+    let result = loop {
+        split_replies(&mut received, &mut reply, &mut typed);
+        if done(&reply) {
+            break Ok(reply);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "terminal did not reply",
+            ));
+        }
+
+        let mut fds = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut fds, 1, remaining.as_millis() as libc::c_int) };
+        if ready < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            break Err(err);
+        }
+        if ready == 0 {
+            continue;
+        }
+        match tty.read(&mut chunk) {
+            Ok(0) => {
+                break Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "terminal closed",
+                ));
+            }
+            Ok(len) => received.extend_from_slice(&chunk[..len]),
+            Err(err) => break Err(err),
+        }
+    };
+
+    // A lone escape or a cut-off sequence left over is typed input as well.
+    typed.append(&mut received);
+    if let Ok(mut keys) = TYPED_AHEAD.lock() {
+        keys.extend(decode_keys(&typed));
+    }
+    result
+}
+
+/// Moves the complete terminal replies at the front of `received` to
+/// `reply` and everything else to `typed`. An escape sequence that may still
+/// be arriving stays in `received`.
+fn split_replies(received: &mut Vec<u8>, reply: &mut Vec<u8>, typed: &mut Vec<u8>) {
+    let mut pos = 0;
+    while pos < received.len() {
+        if received[pos] != ESC {
+            typed.push(received[pos]);
+            pos += 1;
+            continue;
+        }
+        match csi_len(&received[pos..]) {
+            Some(Some(len)) => {
+                let sequence = &received[pos..pos + len];
+                if is_reply(sequence) {
+                    reply.extend_from_slice(sequence);
+                } else {
+                    typed.extend_from_slice(sequence);
+                }
+                pos += len;
+            }
+            // Not a control sequence: Esc, or Alt with a key.
+            None => {
+                typed.push(ESC);
+                pos += 1;
+            }
+            Some(None) => break,
+        }
+    }
+    received.drain(..pos);
+}
+
+/// Length of the control sequence (`ESC [ params final`) at the start of
+/// `bytes`: `None` if it isn't one, `Some(None)` if it isn't complete yet.
+fn csi_len(bytes: &[u8]) -> Option<Option<usize>> {
+    match bytes.get(1) {
+        None => return Some(None),
+        Some(b'[') => {}
+        Some(_) => return None,
+    }
+    for (idx, &byte) in bytes.iter().enumerate().skip(2) {
+        match byte {
+            0x20..=0x3F => {}
+            0x40..=0x7E => return Some(Some(idx + 1)),
+            _ => return None,
+        }
+    }
+    Some(None)
+}
+
+/// Cursor position (`ESC[r;cR`), mode report (`ESC[?2026;1$y`) and device
+/// attributes (`ESC[?...c`) replies, as opposed to keys.
+fn is_reply(sequence: &[u8]) -> bool {
+    let body = &sequence[2..sequence.len() - 1];
+    match sequence[sequence.len() - 1] {
+        b'R' => body.contains(&b';') && body.iter().all(|b| b.is_ascii_digit() || *b == b';'),
+        b'y' => body.starts_with(b"?") && body.ends_with(b"$"),
+        b'c' => body.starts_with(b"?"),
+        _ => false,
+    }
+}
+
+/// Turns raw bytes typed in raw mode into key events: text, Enter, Tab,
+/// Backspace, Esc, Ctrl and Alt combinations, arrows, Home, End and Delete.
+/// Other sequences are dropped.
+fn decode_keys(bytes: &[u8]) -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let byte = bytes[pos];
+        if byte == ESC {
+            if let Some(Some(len)) = csi_len(&bytes[pos..]) {
+                keys.extend(decode_csi(&bytes[pos..pos + len]));
+                pos += len;
+                continue;
+            }
+            // Esc followed by a printable character is Alt with that key.
+            match bytes.get(pos + 1) {
+                Some(&next) if (0x20..0x7F).contains(&next) => {
+                    keys.push(KeyEvent::new(
+                        KeyCode::Char(next as char),
+                        KeyModifiers::ALT,
+                    ));
+                    pos += 2;
+                }
+                _ => {
+                    keys.push(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+                    pos += 1;
+                }
+            }
+            continue;
+        }
+
+        let key = match byte {
+            b'\r' | b'\n' => KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            b'\t' => KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE),
+            0x7F | 0x08 => KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+            0x01..=0x1A => KeyEvent::new(
+                KeyCode::Char((b'a' + byte - 1) as char),
+                KeyModifiers::CONTROL,
+            ),
+            _ => {
+                let len = utf8_len(byte).min(bytes.len() - pos);
+                if let Some(ch) = std::str::from_utf8(&bytes[pos..pos + len])
+                    .ok()
+                    .and_then(|text| text.chars().next())
+                {
+                    keys.push(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+                }
+                pos += len;
+                continue;
+            }
+        };
+        keys.push(key);
+        pos += 1;
+    }
+    keys
+}
+
+fn decode_csi(sequence: &[u8]) -> Option<KeyEvent> {
+    let code = match &sequence[2..] {
+        b"A" => KeyCode::Up,
+        b"B" => KeyCode::Down,
+        b"C" => KeyCode::Right,
+        b"D" => KeyCode::Left,
+        b"H" | b"1~" => KeyCode::Home,
+        b"F" | b"4~" => KeyCode::End,
+        b"3~" => KeyCode::Delete,
+        b"Z" => return Some(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+        _ => return None,
+    };
+    Some(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    }
+}
+
+#[cfg(not(unix))]
+fn read_reply(_tty: &mut File, _done: impl Fn(&[u8]) -> bool) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "terminal queries need /dev/tty",
+    ))
+}
+
+/// Parses `ESC [ row ; col R`, the last one when there are several.
+fn parse_cursor_position(reply: &[u8]) -> Option<(u16, u16)> {
+    let start = reply.iter().rposition(|&b| b == 0x1B)?;
+    let body = std::str::from_utf8(&reply[start..]).ok()?;
+    let body = body.strip_prefix("\x1B[")?.strip_suffix('R')?;
+    let (row, col) = body.split_once(';')?;
+    let row: u16 = row.parse().ok()?;
+    let col: u16 = col.parse().ok()?;
+    Some((col.saturating_sub(1), row.saturating_sub(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(bytes: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut received = bytes.to_vec();
+        let mut reply = Vec::new();
+        let mut typed = Vec::new();
+        split_replies(&mut received, &mut reply, &mut typed);
+        (reply, typed, received)
+    }
+
+    #[test]
+    fn keeps_keys_typed_around_the_reply() {
+        let (reply, typed, rest) = split(b"ab\x1B[A\x1B[12;40Rc");
+        assert_eq!(reply, b"\x1B[12;40R");
+        assert_eq!(typed, b"ab\x1B[Ac");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn separates_mode_and_device_attribute_replies() {
+        let (reply, typed, _) = split(b"\x1B[?2026;2$yx\x1B[?62;22c");
+        assert_eq!(reply, b"\x1B[?2026;2$y\x1B[?62;22c");
+        assert_eq!(typed, b"x");
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_sequence() {
+        let (reply, typed, rest) = split(b"q\x1B[12;4");
+        assert!(reply.is_empty());
+        assert_eq!(typed, b"q");
+        assert_eq!(rest, b"\x1B[12;4");
+    }
+
+    #[test]
+    fn decodes_typed_bytes_into_keys() {
+        let keys = decode_keys("hé\r\x7f\x03\x1B[D\x1Bx\x1B".as_bytes());
+        let expected = [
+            KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Left, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('x'), KeyModifiers::ALT),
+            KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+        ];
+        assert_eq!(keys, expected);
+    }
+
+    /// Records every write it gets.
+    #[derive(Default)]
+    struct Writes(Vec<Vec<u8>>);
+
+    impl Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_frame_reaches_the_terminal_in_one_write() {
+        let mut writer = FrameWriter::new(Writes::default());
+        write!(writer, "\x1B[?25l").unwrap();
+        writer.write_all(b"first line\r\n").unwrap();
+        writer.write_all(b"second line").unwrap();
+        assert!(writer.inner.0.is_empty());
+
+        writer.flush().unwrap();
+        assert_eq!(
+            writer.inner.0,
+            vec![b"\x1B[?25lfirst line\r\nsecond line".to_vec()]
+        );
+
+        // Nothing new means nothing written.
+        writer.flush().unwrap();
+        assert_eq!(writer.inner.0.len(), 1);
+    }
+
+    #[test]
+    fn synchronized_output_needs_a_set_or_reset_mode_report() {
+        assert!(reports_synchronized_output(b"\x1B[?2026;2$y\x1B[?62;22c"));
+        assert!(reports_synchronized_output(b"\x1B[?2026;1$y\x1B[?62c"));
+        assert!(!reports_synchronized_output(b"\x1B[?2026;0$y\x1B[?62c"));
+        assert!(!reports_synchronized_output(b"\x1B[?2026;4$y\x1B[?62c"));
+        // Only the device attributes came back: DECRQM is not supported.
+        assert!(!reports_synchronized_output(b"\x1B[?62;22c"));
+    }
+
+    #[test]
+    fn parses_the_cursor_position() {
+        assert_eq!(parse_cursor_position(b"\x1B[5;12R"), Some((11, 4)));
+    }
+}
@@ -0,0 +1,223 @@
+//! Frames as standalone HTML and SVG, for docs and reviews.
+//!
+//! Both formats draw light text on a dark background, like a default
+//! terminal. Colors are written out in full, so the result needs no
+//! stylesheet.
+
+use crate::frame::{Frame, Line};
+use crate::style::{Style, named_rgb};
+
+type Rgb = (u8, u8, u8);
+
+const FOREGROUND: Rgb = (212, 212, 212);
+const BACKGROUND: Rgb = (30, 30, 30);
+
+const FONT_SIZE: f32 = 14.0;
+/// Width of one cell; most monospace fonts are about 0.6em wide.
+const CELL_WIDTH: f32 = FONT_SIZE * 0.6;
+const LINE_HEIGHT: f32 = FONT_SIZE * 1.3;
+const PADDING: f32 = 12.0;
+
+/// A `<pre>` block with a `<span>` for every run of styled text.
+pub fn to_html(frame: &Frame) -> String {
+    let mut html = format!(
+        "<pre style=\"background:{};color:{};font-family:monospace;line-height:1.3;padding:1em\">",
+        hex(BACKGROUND),
+        hex(FOREGROUND)
+    );
+    for (idx, line) in frame.lines().iter().enumerate() {
+        if idx > 0 {
+            html.push('\n');
+        }
+        for (style, text, _) in runs(line) {
+            let css = css(&style);
+            if css.is_empty() {
+                html.push_str(&escape(&text));
+            } else {
+                html.push_str(&format!("<span style=\"{}\">{}</span>", css, escape(&text)));
+            }
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+/// A "terminal screenshot": every run of equally styled text becomes a
+/// `<text>` stretched to its cell width, so box drawing and wide characters
+/// stay on the grid whatever font the viewer picks.
+pub fn to_svg(frame: &Frame) -> String {
+    let columns = frame
+        .lines()
+        .iter()
+        .map(|line| line.width())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let rows = frame.lines().len().max(1);
+    let width = columns as f32 * CELL_WIDTH + 2.0 * PADDING;
+    let height = rows as f32 * LINE_HEIGHT + 2.0 * PADDING;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" viewBox=\"0 0 {width:.1} {height:.1}\">\n"
+    );
+    svg.push_str(&format!(
+        "<rect width=\"100%\" height=\"100%\" rx=\"6\" fill=\"{}\"/>\n",
+        hex(BACKGROUND)
+    ));
+    svg.push_str(&format!(
+        "<g font-family=\"monospace\" font-size=\"{FONT_SIZE}\" fill=\"{}\">\n",
+        hex(FOREGROUND)
+    ));
+
+    for (row, line) in frame.lines().iter().enumerate() {
+        let top = PADDING + row as f32 * LINE_HEIGHT;
+        let mut col = 0;
+        for (style, text, width) in runs(line) {
+            svg.push_str(&svg_text(&style, &text, col, width, top));
+            col += width;
+        }
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+fn svg_text(style: &Style, text: &str, col: usize, columns: usize, top: f32) -> String {
+    let x = PADDING + col as f32 * CELL_WIDTH;
+    let width = columns as f32 * CELL_WIDTH;
+    let (foreground, background) = colors(style);
+
+    let mut out = String::new();
+    if let Some(background) = background {
+        out.push_str(&format!(
+            "<rect x=\"{x:.1}\" y=\"{top:.1}\" width=\"{width:.1}\" height=\"{LINE_HEIGHT:.1}\" fill=\"{}\"/>\n",
+            hex(background)
+        ));
+    }
+    if text.trim().is_empty() && text_decoration(style).is_none() {
+        return out;
+    }
+
+    let mut attributes = String::new();
+    if let Some(foreground) = foreground {
+        attributes.push_str(&format!(" fill=\"{}\"", hex(foreground)));
+    }
+    if style.bold() {
+        attributes.push_str(" font-weight=\"bold\"");
+    }
+    if style.italic() {
+        attributes.push_str(" font-style=\"italic\"");
+    }
+    if style.dim() {
+        attributes.push_str(" opacity=\"0.6\"");
+    }
+    if let Some(decoration) = text_decoration(style) {
+        attributes.push_str(&format!(" text-decoration=\"{}\"", decoration));
+    }
+
+    // Text sits on its baseline, roughly 0.8 of the line down.
+    let baseline = top + LINE_HEIGHT * 0.8;
+    out.push_str(&format!(
+        "<text x=\"{x:.1}\" y=\"{baseline:.1}\" textLength=\"{width:.1}\" lengthAdjust=\"spacingAndGlyphs\" xml:space=\"preserve\"{attributes}>{}</text>\n",
+        escape(text)
+    ));
+    out
+}
+
+/// The line's text grouped into runs of neighbouring spans with the same
+/// style, with the cells each run takes. Word wrapping leaves one span per
+/// word, which would bloat the output.
+fn runs(line: &Line) -> Vec<(Style, String, usize)> {
+    let mut runs: Vec<(Style, String, usize)> = Vec::new();
+    for span in line.spans() {
+        match runs.last_mut() {
+            Some((style, text, width)) if style == span.style() => {
+                text.push_str(span.text());
+                *width += span.width();
+            }
+            _ => runs.push((span.style().clone(), span.text().to_string(), span.width())),
+        }
+    }
+    runs
+}
+
+/// Inline CSS for `style`, empty for plain text.
+fn css(style: &Style) -> String {
+    let (foreground, background) = colors(style);
+    let mut declarations = Vec::new();
+    if let Some(color) = foreground {
+        declarations.push(format!("color:{}", hex(color)));
+    }
+    if let Some(color) = background {
+        declarations.push(format!("background:{}", hex(color)));
+    }
+    if style.bold() {
+        declarations.push("font-weight:bold".to_string());
+    }
+    if style.italic() {
+        declarations.push("font-style:italic".to_string());
+    }
+    if style.dim() {
+        declarations.push("opacity:0.6".to_string());
+    }
+    if let Some(decoration) = text_decoration(style) {
+        declarations.push(format!("text-decoration:{}", decoration));
+    }
+    declarations.join(";")
+}
+
+/// Foreground and background to draw, with reverse video applied. `None`
+/// means the default, which needs no markup.
+fn colors(style: &Style) -> (Option<Rgb>, Option<Rgb>) {
+    let foreground = style.color().map(named_rgb);
+    let background = style.background().map(named_rgb);
+    if style.reverse() {
+        (
+            Some(background.unwrap_or(BACKGROUND)),
+            Some(foreground.unwrap_or(FOREGROUND)),
+        )
+    } else {
+        (foreground, background)
+    }
+}
+
+fn text_decoration(style: &Style) -> Option<&'static str> {
+    match (style.underline(), style.strikethrough()) {
+        (true, true) => Some("underline line-through"),
+        (true, false) => Some("underline"),
+        (false, true) => Some("line-through"),
+        (false, false) => None,
+    }
+}
+
+fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+
+    #[test]
+    fn svg_runs_take_the_cells_the_layout_gives_them() {
+        let mut frame = Frame::new();
+        frame.ensure_line();
+        let line = frame.current_line_mut();
+        line.push(Span::new("名前"));
+        line.push(Span::new("ok").with_style(Style::new().with_bold()));
+        let svg = frame.to_svg();
+
+        // "名前" is four cells wide, so the bold run starts at column four.
+        let cells = |columns: f32| PADDING + columns * CELL_WIDTH;
+        assert!(svg.contains(&format!("textLength=\"{:.1}\"", 4.0 * CELL_WIDTH)));
+        assert!(svg.contains(&format!("<text x=\"{:.1}\"", cells(4.0))));
+    }
+}
@@ -1,4 +1,9 @@
-use crate::span::Span;
+use crate::input::NodeId;
+use crate::sgr::SgrState;
+use crate::span::{HitTarget, Span};
+use crate::style::{ColorSupport, Style};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct Line {
@@ -27,11 +32,79 @@ impl Line {
     pub fn width(&self) -> usize {
         self.spans.iter().map(|s| s.width()).sum()
     }
+
+    /// Column of the first cell that is drawn differently in `other`, or
+    /// `None` when both lines produce the same cells.
+    pub fn diff_column(&self, other: &Line) -> Option<usize> {
+        let mut ours = self.styled_graphemes();
+        let mut theirs = other.styled_graphemes();
+        let mut col = 0;
+
+        loop {
+            match (ours.next(), theirs.next()) {
+                (None, None) => return None,
+                (Some(a), Some(b)) if a == b => col += a.0.width(),
+                _ => return Some(col),
+            }
+        }
+    }
+
+    /// The part of the line drawn from display column `col` onwards.
+    pub fn slice_from(&self, col: usize) -> Line {
+        let mut line = Line::new();
+        let mut skipped = 0;
+        for span in &self.spans {
+            if skipped >= col {
+                line.push(span.clone());
+                continue;
+            }
+            let span_width = span.width();
+            if skipped + span_width <= col {
+                skipped += span_width;
+                continue;
+            }
+            let (_, tail) = span.split_at_width(col - skipped);
+            skipped = col;
+            if let Some(tail) = tail {
+                line.push(tail);
+            }
+        }
+        line
+    }
+
+    /// The span drawn at display column `col`, with the column inside it.
+    pub fn span_at(&self, col: usize) -> Option<(&Span, usize)> {
+        let mut start = 0;
+        for span in &self.spans {
+            let end = start + span.width();
+            if col < end {
+                return Some((span, col - start));
+            }
+            start = end;
+        }
+        None
+    }
+
+    fn styled_graphemes(&self) -> impl Iterator<Item = (&str, &Style)> {
+        self.spans
+            .iter()
+            .flat_map(|span| span.text().graphemes(true).map(move |g| (g, span.style())))
+    }
+}
+
+/// What a mouse click on a frame landed on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hit {
+    pub id: NodeId,
+    /// Display column within the input's content, when the click was on the
+    /// content rather than its label or brackets.
+    pub offset: Option<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct Frame {
     lines: Vec<Line>,
+    cursor: Option<(usize, usize)>,
 }
 
 impl Frame {
@@ -47,6 +120,15 @@ impl Frame {
         &mut self.lines
     }
 
+    /// Column and line index of the cursor anchor, if one was laid out.
+    pub fn cursor(&self) -> Option<(usize, usize)> {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: Option<(usize, usize)>) {
+        self.cursor = cursor;
+    }
+
     pub fn ensure_line(&mut self) {
         if self.lines.is_empty() {
             self.lines.push(Line::new());
@@ -62,8 +144,93 @@ impl Frame {
         self.lines.push(Line::new());
     }
 
+    /// Finds the input drawn at display column `col` of line `line`.
+    /// Content that wrapped over several lines is measured from its start.
+    pub fn hit_test(&self, col: usize, line: usize) -> Option<Hit> {
+        let (span, col_in_span) = self.lines.get(line)?.span_at(col)?;
+        let id = match span.target()? {
+            HitTarget::Field(id) => {
+                return Some(Hit {
+                    id: id.clone(),
+                    offset: None,
+                });
+            }
+            HitTarget::Content(id) => id,
+        };
+
+        let target = HitTarget::Content(id.clone());
+        let content_width = |spans: &[Span], end: usize| -> usize {
+            let mut start = 0;
+            let mut width = 0;
+            for span in spans {
+                if start >= end {
+                    break;
+                }
+                if span.target() == Some(&target) {
+                    width += span.width();
+                }
+                start += span.width();
+            }
+            width
+        };
+        let span_start = col - col_in_span;
+        let offset = self.lines[..line]
+            .iter()
+            .map(|l| content_width(l.spans(), usize::MAX))
+            .sum::<usize>()
+            + content_width(self.lines[line].spans(), span_start);
+        Some(Hit {
+            id: id.clone(),
+            offset: Some(offset + col_in_span),
+        })
+    }
+
+    /// The text of every line without styles, trailing blanks trimmed,
+    /// joined by newlines.
+    pub fn to_plain(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| {
+                let text: String = line.spans().iter().map(|span| span.text()).collect();
+                text.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The lines with SGR escape sequences for their styles, colors reduced
+    /// to `support`. Every line ends with default attributes.
+    pub fn to_ansi(&self, support: ColorSupport) -> String {
+        let mut out = Vec::new();
+        let mut sgr = SgrState::new();
+        for (idx, line) in self.lines.iter().enumerate() {
+            if idx > 0 {
+                out.push(b'\n');
+            }
+            for span in line.spans() {
+                // Writing into a Vec can't fail.
+                let _ = sgr.transition(&mut out, &span.style().downsample(support));
+                out.extend_from_slice(span.text().as_bytes());
+            }
+            let _ = sgr.reset(&mut out);
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// A self-contained HTML `<pre>` block; see [`crate::export`].
+    pub fn to_html(&self) -> String {
+        crate::export::to_html(self)
+    }
+
+    /// A self-contained SVG image of the frame; see [`crate::export`].
+    pub fn to_svg(&self) -> String {
+        crate::export::to_svg(self)
+    }
+
+    /// Drops empty lines at the end, except the one holding the cursor.
     pub fn trim_trailing_empty(&mut self) {
-        while self.lines.last().map(|l| l.is_empty()).unwrap_or(false) {
+        let keep = self.cursor.map_or(0, |(_, line)| line + 1);
+        while self.lines.len() > keep && self.lines.last().map(|l| l.is_empty()).unwrap_or(false) {
             self.lines.pop();
         }
         if self.lines.is_empty() {
@@ -1,6 +1,367 @@
-use crate::frame::Frame;
+use crate::frame::{Frame, Line};
 use crate::node::Node;
+use crate::render_context::RenderContext;
 use crate::span::{Span, Wrap};
+use crate::style::Style;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How much of a row's width a column takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnWidth {
+    /// Exactly this many cells, or whatever is left when the row is narrower.
+    Fixed(usize),
+    /// A share of the width left over by fixed columns, proportional to the
+    /// weight.
+    Flex(u16),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    spans: Vec<Span>,
+    width: ColumnWidth,
+    min_width: usize,
+}
+
+impl Column {
+    pub fn new(spans: Vec<Span>, width: ColumnWidth) -> Self {
+        Self {
+            spans,
+            width,
+            min_width: 0,
+        }
+    }
+
+    pub fn fixed(spans: Vec<Span>, width: usize) -> Self {
+        Self::new(spans, ColumnWidth::Fixed(width))
+    }
+
+    pub fn flex(spans: Vec<Span>) -> Self {
+        Self::new(spans, ColumnWidth::Flex(1))
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// Cells a flexible column keeps even when fixed columns would take
+    /// them; those columns shrink instead. Ignored for fixed columns.
+    pub fn with_min_width(mut self, min_width: usize) -> Self {
+        self.min_width = min_width;
+        self
+    }
+
+    pub fn width(&self) -> ColumnWidth {
+        self.width
+    }
+}
+
+/// Columns laid out side by side. Each column wraps within its own width and
+/// the row is as tall as its tallest column.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Row {
+    columns: Vec<Column>,
+    gap: usize,
+}
+
+impl Row {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self { columns, gap: 1 }
+    }
+
+    /// Blank cells between neighbouring columns.
+    pub fn with_gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Splits `width` between the columns: fixed columns first, in order,
+    /// then flexible ones by weight. The minimum widths of flexible columns
+    /// are set aside before fixed columns get theirs.
+    fn column_widths(&self, width: usize) -> Vec<usize> {
+        let gaps = self.gap * self.columns.len().saturating_sub(1);
+        let available = width.saturating_sub(gaps);
+        let reserved: usize = self
+            .columns
+            .iter()
+            .filter(|column| matches!(column.width, ColumnWidth::Flex(_)))
+            .map(|column| column.min_width)
+            .sum();
+        let mut remaining = available.saturating_sub(reserved);
+        let mut widths: Vec<usize> = self
+            .columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fixed(w) => {
+                    let w = w.min(remaining);
+                    remaining -= w;
+                    w
+                }
+                ColumnWidth::Flex(_) => 0,
+            })
+            .collect();
+
+        let total_weight: usize = self
+            .columns
+            .iter()
+            .filter_map(|column| match column.width {
+                ColumnWidth::Flex(weight) => Some(weight as usize),
+                ColumnWidth::Fixed(_) => None,
+            })
+            .sum();
+        if total_weight == 0 {
+            return widths;
+        }
+
+        // Each flexible column gets its minimum plus a share of what is left.
+        // When even the minimums don't fit, all of the width is shared out.
+        let minimums_fit = reserved <= available;
+        let flex_width = if minimums_fit { remaining } else { available };
+        let mut weight_seen = 0;
+        let mut given = 0;
+        for (column, w) in self.columns.iter().zip(widths.iter_mut()) {
+            if let ColumnWidth::Flex(weight) = column.width {
+                // Hand out cumulative shares so rounding never loses a cell.
+                weight_seen += weight as usize;
+                let share = flex_width * weight_seen / total_weight;
+                *w = share - given;
+                given = share;
+                if minimums_fit {
+                    *w += column.min_width;
+                }
+            }
+        }
+        widths
+    }
+
+    /// Lays the columns out side by side. A cursor anchor in any column is
+    /// reported as (column, line) within the row.
+    fn compose(&self, width: usize) -> (Vec<Line>, Option<(usize, usize)>) {
+        let widths = self.column_widths(width);
+        let frames: Vec<Frame> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, &w)| {
+                let mut ctx = LayoutContext::new(w, 0);
+                ctx.place_spans(column.spans.clone());
+                ctx.finish()
+            })
+            .collect();
+
+        let mut cursor = None;
+        let mut x = 0;
+        for (frame, &w) in frames.iter().zip(&widths) {
+            if let Some((col, line)) = frame.cursor() {
+                // A column squeezed to nothing still keeps the cursor on the row.
+                cursor = Some(((x + col).min(width.saturating_sub(1)), line));
+                break;
+            }
+            x += w + self.gap;
+        }
+
+        let height = frames.iter().map(|f| f.lines().len()).max().unwrap_or(0);
+        let last = frames.len().saturating_sub(1);
+        let lines = (0..height)
+            .map(|row| {
+                let mut line = Line::new();
+                let mut pad = 0;
+                for (idx, (frame, &w)) in frames.iter().zip(&widths).enumerate() {
+                    let Some(part) = frame.lines().get(row).filter(|l| !l.is_empty()) else {
+                        pad += w + if idx < last { self.gap } else { 0 };
+                        continue;
+                    };
+                    if pad > 0 {
+                        line.push(Span::new(" ".repeat(pad)));
+                    }
+                    for span in part.spans() {
+                        line.push(span.clone());
+                    }
+                    pad = w.saturating_sub(part.width()) + if idx < last { self.gap } else { 0 };
+                }
+                line
+            })
+            .collect();
+        (lines, cursor)
+    }
+}
+
+/// Blocks laid out within a narrower area: indented under an optional
+/// title, or framed by a border with the title in its top edge.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Container {
+    children: Vec<Block>,
+    title: Option<Vec<Span>>,
+    indent: usize,
+    border: Option<Style>,
+}
+
+impl Container {
+    pub fn new(children: Vec<Block>) -> Self {
+        Self {
+            children,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_children(mut self, children: Vec<Block>) -> Self {
+        self.children = children;
+        self
+    }
+
+    pub fn with_title(mut self, title: Vec<Span>) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    /// Blank cells in front of every child line. Ignored with a border.
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn with_border(mut self, style: Style) -> Self {
+        self.border = Some(style);
+        self
+    }
+
+    pub fn children(&self) -> &[Block] {
+        &self.children
+    }
+
+    /// Columns left for the children inside `width`.
+    pub fn inner_width(&self, width: usize) -> usize {
+        match self.border {
+            Some(_) if width >= 5 => width - 4,
+            _ => width - self.indent.min(width.saturating_sub(1)),
+        }
+    }
+
+    /// Lays the children out and decorates them. A cursor anchor among them
+    /// is reported as (column, line) within the container.
+    fn compose(&self, width: usize) -> (Vec<Line>, Option<(usize, usize)>) {
+        match &self.border {
+            // Too narrow for "│ x │": fall back to plain children.
+            Some(style) if width >= 5 => self.compose_bordered(width, style),
+            _ => self.compose_indented(width),
+        }
+    }
+
+    fn compose_children(&self, width: usize) -> Frame {
+        let mut ctx = LayoutContext::new(width, 0);
+        for block in &self.children {
+            ctx.place_block(block.clone());
+        }
+        ctx.finish()
+    }
+
+    fn compose_indented(&self, width: usize) -> (Vec<Line>, Option<(usize, usize)>) {
+        let mut lines = Vec::new();
+        if let Some(title) = &self.title {
+            let mut ctx = LayoutContext::new(width, 0);
+            ctx.place_spans(title.clone());
+            lines.extend(ctx.finish().lines().iter().cloned());
+        }
+
+        let indent = width - self.inner_width(width);
+        let inner = self.compose_children(width - indent);
+        let cursor = inner
+            .cursor()
+            .map(|(col, line)| (indent + col, lines.len() + line));
+        for line in inner.lines() {
+            let mut indented = Line::new();
+            if indent > 0 && !line.is_empty() {
+                indented.push(Span::new(" ".repeat(indent)));
+            }
+            for span in line.spans() {
+                indented.push(span.clone());
+            }
+            lines.push(indented);
+        }
+        (lines, cursor)
+    }
+
+    fn compose_bordered(&self, width: usize, style: &Style) -> (Vec<Line>, Option<(usize, usize)>) {
+        let inner_width = self.inner_width(width);
+        let edge = |text: String| Span::new(text).with_style(style.clone());
+
+        let mut top = Line::new();
+        top.push(edge("┌".to_string()));
+        let mut used = 1;
+        if let Some(title) = &self.title {
+            // "┌─ Title ─┐": the title keeps at least one dash on each side.
+            let mut room = width.saturating_sub(6);
+            let mut fitted = Vec::new();
+            for span in title {
+                let (head, rest) = span.split_at_width(room);
+                room -= head.width();
+                fitted.push(head);
+                if rest.is_some() {
+                    break;
+                }
+            }
+            let title_width: usize = fitted.iter().map(Span::width).sum();
+            if title_width > 0 {
+                top.push(edge("─ ".to_string()));
+                used += 2 + title_width + 1;
+                for span in fitted {
+                    top.push(span);
+                }
+                top.push(edge(" ".to_string()));
+            }
+        }
+        top.push(edge(format!("{}┐", "─".repeat(width - 1 - used))));
+
+        let inner = self.compose_children(inner_width);
+        let cursor = inner.cursor().map(|(col, line)| (2 + col, 1 + line));
+        let mut lines = vec![top];
+        for line in inner.lines() {
+            let mut framed = Line::new();
+            framed.push(edge("│ ".to_string()));
+            for span in line.spans() {
+                framed.push(span.clone());
+            }
+            let pad = inner_width.saturating_sub(line.width());
+            framed.push(edge(format!("{} │", " ".repeat(pad))));
+            lines.push(framed);
+        }
+
+        let mut bottom = Line::new();
+        bottom.push(edge(format!("└{}┘", "─".repeat(width - 2))));
+        lines.push(bottom);
+        (lines, cursor)
+    }
+}
+
+/// One vertical slot of a layout: spans flowing over full-width lines, a
+/// row of columns, or a container of further blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    Spans(Vec<Span>),
+    Row(Row),
+    Container(Container),
+}
+
+impl From<Vec<Span>> for Block {
+    fn from(spans: Vec<Span>) -> Self {
+        Block::Spans(spans)
+    }
+}
+
+impl From<Row> for Block {
+    fn from(row: Row) -> Self {
+        Block::Row(row)
+    }
+}
+
+impl From<Container> for Block {
+    fn from(container: Container) -> Self {
+        Block::Container(container)
+    }
+}
 
 #[derive(Clone, Debug, Default)]
 pub struct Layout {
@@ -17,43 +378,74 @@ impl Layout {
         self
     }
 
-    pub fn compose(&self, nodes: &[Node], width: u16) -> Frame {
-        self.compose_with(nodes, width, |node| {
-            node.render(false, &crate::theme::Theme::default_theme())
-        })
+    /// Lays out `nodes` at `ctx.width` columns.
+    pub fn compose(&self, nodes: &[Node], ctx: &RenderContext) -> Frame {
+        self.compose_with(nodes, ctx, |node, ctx| node.render(false, ctx))
     }
 
-    pub fn compose_with<F>(&self, nodes: &[Node], width: u16, render: F) -> Frame
+    /// Lays out `nodes` with `render` producing the spans of each text and
+    /// input. Containers are walked recursively, their children rendered
+    /// with the width left inside them.
+    pub fn compose_with<F>(&self, nodes: &[Node], ctx: &RenderContext, render: F) -> Frame
     where
-        F: Fn(&Node) -> Vec<Span>,
+        F: Fn(&Node, &RenderContext) -> Vec<Span>,
     {
-        let mut ctx = LayoutContext::new(width as usize, self.margin);
-
-        for node in nodes {
-            ctx.place_spans(render(node));
-        }
-
-        ctx.finish()
+        let inner = ctx.with_width(ctx.width.saturating_sub(self.margin));
+        self.place_blocks(node_blocks(nodes, &inner, &render), ctx.width)
     }
 
     pub fn compose_spans<I>(&self, spans_list: I, width: u16) -> Frame
     where
         I: IntoIterator<Item = Vec<Span>>,
     {
-        let mut ctx = LayoutContext::new(width as usize, self.margin);
+        self.compose_blocks(spans_list.into_iter().map(Block::Spans), width)
+    }
 
-        for spans in spans_list {
-            ctx.place_spans(spans);
+    pub fn compose_blocks<I>(&self, blocks: I, width: u16) -> Frame
+    where
+        I: IntoIterator<Item = Block>,
+    {
+        self.place_blocks(blocks, width as usize)
+    }
+
+    fn place_blocks<I>(&self, blocks: I, width: usize) -> Frame
+    where
+        I: IntoIterator<Item = Block>,
+    {
+        let mut ctx = LayoutContext::new(width, self.margin);
+
+        for block in blocks {
+            ctx.place_block(block);
         }
 
         ctx.finish()
     }
 }
 
+fn node_blocks<F>(nodes: &[Node], ctx: &RenderContext, render: &F) -> Vec<Block>
+where
+    F: Fn(&Node, &RenderContext) -> Vec<Span>,
+{
+    nodes
+        .iter()
+        .map(|node| match node.container(ctx.theme) {
+            Some(container) => {
+                let inner = ctx.with_width(container.inner_width(ctx.width));
+                let children = node_blocks(node.children(), &inner, render);
+                Block::Container(container.with_children(children))
+            }
+            None => Block::Spans(render(node, ctx)),
+        })
+        .collect()
+}
+
 struct LayoutContext {
     frame: Frame,
     width: usize,
+    margin: usize,
     current_width: usize,
+    /// The current line was started by wrapping, not by an explicit break.
+    wrapped: bool,
 }
 
 impl LayoutContext {
@@ -64,17 +456,82 @@ impl LayoutContext {
         Self {
             frame,
             width,
+            margin,
             current_width: 0,
+            wrapped: false,
+        }
+    }
+
+    fn place_block(&mut self, block: Block) {
+        let (lines, cursor) = match block {
+            Block::Spans(spans) => return self.place_spans(spans),
+            Block::Row(row) => row.compose(self.width),
+            Block::Container(container) => container.compose(self.width),
+        };
+        if let Some((col, line)) = cursor {
+            self.set_cursor(self.margin + col, self.line_index() + line);
+        }
+        for line in lines {
+            self.place_line(line);
         }
     }
 
     fn place_spans(&mut self, spans: Vec<Span>) {
+        // Break opportunities come from the text of the whole line: a word
+        // may run across spans, as in `[bold]wo[/]rd` or around the cursor
+        // anchor, so its pieces are collected until the whitespace after it.
+        let mut word = Vec::new();
         for span in spans {
+            if span.is_cursor_anchor() {
+                word.push(span);
+                continue;
+            }
+            if span.wrap() == Wrap::Word && span.text() != "\n" {
+                for token in split_words(span.text()) {
+                    let piece = span.clone_with_text(token);
+                    if token.starts_with(char::is_whitespace) {
+                        self.place_word(std::mem::take(&mut word));
+                        self.place_space(piece);
+                    } else {
+                        word.push(piece);
+                    }
+                }
+                continue;
+            }
+
+            self.place_word(std::mem::take(&mut word));
             if span.text() == "\n" {
                 self.new_line();
-                continue;
+            } else {
+                self.place_span(span);
             }
-            self.place_span(span);
+        }
+        self.place_word(word);
+        self.new_line();
+    }
+
+    /// Records where the cursor goes. On a full line that is the start of
+    /// the next one, which is where the following text would continue.
+    fn place_cursor(&mut self) {
+        if self.width > 0 && self.current_width >= self.width {
+            self.wrap_line();
+        }
+        let col = self.current_width.min(self.width.saturating_sub(1));
+        self.set_cursor(self.margin + col, self.line_index());
+    }
+
+    fn set_cursor(&mut self, col: usize, line: usize) {
+        self.frame.set_cursor(Some((col, line)));
+    }
+
+    fn line_index(&self) -> usize {
+        self.frame.lines().len().saturating_sub(1)
+    }
+
+    /// Places an already laid out line as is, then breaks.
+    fn place_line(&mut self, line: Line) {
+        for span in line.spans() {
+            self.push_span(span.clone());
         }
         self.new_line();
     }
@@ -87,6 +544,7 @@ impl LayoutContext {
         match span.wrap() {
             Wrap::No => self.place_no_wrap(span),
             Wrap::Yes => self.place_wrap(span),
+            Wrap::Word => self.place_word(vec![span]),
         }
     }
 
@@ -108,7 +566,7 @@ impl LayoutContext {
     fn place_wrap(&mut self, mut span: Span) {
         while span.width() > 0 {
             if self.current_width >= self.width {
-                self.new_line();
+                self.wrap_line();
             }
 
             let available = self.available_width();
@@ -118,10 +576,15 @@ impl LayoutContext {
             }
 
             let (head, tail) = span.split_at_width(available);
+            if head.width() == 0 && self.current_width == 0 {
+                // Not even one character fits on an empty line.
+                self.push_span(span);
+                return;
+            }
             if head.width() > 0 {
                 self.push_span(head);
             }
-            self.new_line();
+            self.wrap_line();
 
             match tail {
                 Some(rest) => span = rest,
@@ -130,15 +593,98 @@ impl LayoutContext {
         }
     }
 
+    /// Places the pieces of one word, moving the whole word to the next line
+    /// when it doesn't fit on this one. Cursor anchors among the pieces go
+    /// with it.
+    fn place_word(&mut self, pieces: Vec<Span>) {
+        let word_width: usize = pieces.iter().map(Span::width).sum();
+        if word_width > self.available_width() && self.current_width > 0 {
+            self.wrap_line();
+        }
+        for piece in pieces {
+            if piece.is_cursor_anchor() {
+                self.place_cursor();
+            } else if self.width > 0 {
+                self.place_long_word(piece);
+            }
+        }
+    }
+
+    /// Whitespace between words: dropped at the start of a wrapped line, and
+    /// turned into the line break when it reaches the edge.
+    fn place_space(&mut self, space: Span) {
+        if self.width == 0 || (self.current_width == 0 && self.wrapped) {
+            return;
+        }
+        if space.width() >= self.available_width() {
+            self.wrap_line();
+        } else {
+            self.push_span(space);
+        }
+    }
+
+    /// Places a word, splitting it over several lines when it is wider than
+    /// a whole line. Splits between two letters get a hyphen.
+    fn place_long_word(&mut self, mut word: Span) {
+        while word.width() > self.available_width() {
+            let available = self.available_width();
+            if available > 1 {
+                let (head, tail) = word.split_at_width(available - 1);
+                if let Some(tail) = tail
+                    && is_mid_word(&head, &tail)
+                {
+                    let hyphen = word.clone_with_text("-");
+                    self.push_span(head);
+                    self.push_span(hyphen);
+                    self.wrap_line();
+                    word = tail;
+                    continue;
+                }
+            }
+
+            let (head, tail) = word.split_at_width(available);
+            let Some(tail) = tail else {
+                break;
+            };
+            if head.width() == 0 {
+                if self.current_width == 0 {
+                    // Not even one character fits on an empty line.
+                    break;
+                }
+                self.wrap_line();
+                continue;
+            }
+            self.push_span(head);
+            self.wrap_line();
+            word = tail;
+        }
+        self.push_span(word);
+    }
+
     fn push_span(&mut self, span: Span) {
         let w = span.width();
+        if w == 0 {
+            return;
+        }
+        if self.current_width == 0 && self.margin > 0 {
+            self.frame
+                .current_line_mut()
+                .push(Span::new(" ".repeat(self.margin)));
+        }
         self.frame.current_line_mut().push(span);
         self.current_width += w;
+        self.wrapped = false;
     }
 
     fn new_line(&mut self) {
         self.frame.new_line();
         self.current_width = 0;
+        self.wrapped = false;
+    }
+
+    fn wrap_line(&mut self) {
+        self.new_line();
+        self.wrapped = true;
     }
 
     fn available_width(&self) -> usize {
@@ -150,3 +696,93 @@ impl LayoutContext {
         self.frame
     }
 }
+
+/// Splits text into alternating runs of whitespace and non-whitespace.
+fn split_words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (idx, ch) in text.char_indices() {
+        let is_space = ch.is_whitespace();
+        if in_space.is_some_and(|prev| prev != is_space) {
+            tokens.push(&text[start..idx]);
+            start = idx;
+        }
+        in_space = Some(is_space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn is_mid_word(head: &Span, tail: &Span) -> bool {
+    // Judge by the base character, so "é" written with a combining accent
+    // still counts as a letter.
+    let before = head
+        .text()
+        .graphemes(true)
+        .next_back()
+        .and_then(|g| g.chars().next());
+    let after = tail.text().chars().next();
+    before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup;
+    use crate::theme::Theme;
+
+    fn compose(spans: Vec<Span>, width: u16) -> Frame {
+        Layout::new().compose_spans([spans], width)
+    }
+
+    #[test]
+    fn fixed_columns_shrink_to_keep_a_flex_minimum() {
+        let row = Row::new(vec![
+            Column::fixed(Vec::new(), 14),
+            Column::flex(Vec::new()).with_min_width(10),
+        ])
+        .with_gap(0);
+        assert_eq!(row.column_widths(30), vec![14, 16]);
+        assert_eq!(row.column_widths(16), vec![6, 10]);
+        assert_eq!(row.column_widths(8), vec![0, 8]);
+    }
+
+    #[test]
+    fn a_cursor_in_a_squeezed_column_stays_on_the_row() {
+        let row = Row::new(vec![
+            Column::fixed(vec![Span::new("Meeting Time: ")], 14),
+            Column::flex(vec![Span::new("hello"), Span::cursor_anchor()]),
+        ])
+        .with_gap(0);
+        let frame = Layout::new().compose_blocks([Block::Row(row)], 8);
+        assert_eq!(frame.cursor().map(|(col, _)| col), Some(7));
+    }
+
+    #[test]
+    fn word_wrap_breaks_between_words() {
+        let spans = vec![Span::new("one two three").with_wrap(Wrap::Word)];
+        assert_eq!(compose(spans, 9).to_plain(), "one two\nthree");
+    }
+
+    #[test]
+    fn a_word_split_across_styles_wraps_as_a_whole() {
+        let theme = Theme::default_theme();
+        let spans = markup::parse("say [bold]wo[/]rd", &Style::new(), &theme);
+        assert_eq!(compose(spans, 6).to_plain(), "say\nword");
+    }
+
+    #[test]
+    fn the_cursor_anchor_moves_with_its_word() {
+        let spans = vec![
+            Span::new("abc wo").with_wrap(Wrap::Word),
+            Span::cursor_anchor(),
+            Span::new("rd").with_wrap(Wrap::Word),
+        ];
+        let frame = compose(spans, 6);
+        assert_eq!(frame.to_plain(), "abc\nword");
+        assert_eq!(frame.cursor(), Some((2, 1)));
+    }
+}
@@ -0,0 +1,207 @@
+//! Inline style markup for prompts, hints and text nodes.
+//!
+//! `[bold red]Warning:[/] disk is [hint]almost[/] full` opens a style with
+//! `[...]` and closes the innermost one with `[/]`. A tag holds any mix of
+//! attributes (`bold`, `italic`, ...), colors (`red`, `#ff8800`,
+//! `color(208)`), `on <color>` for the background, and theme style names
+//! (`prompt`, `hint`, `error`, ...). Brackets that don't form a valid tag,
+//! like `[1/3]`, stay as they are; `\[` always gives a literal `[`.
+
+use crate::span::{Span, Wrap};
+use crate::style::{Color, Style};
+use crate::theme::Theme;
+
+enum Tag {
+    Open(Style),
+    Close,
+}
+
+/// Splits `text` into word-wrapping spans, each with `base` merged with the
+/// styles of the tags around it.
+pub fn parse(text: &str, base: &Style, theme: &Theme) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut stack = vec![base.clone()];
+    let mut current = String::new();
+
+    let mut chars = text.char_indices();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '\\' if matches!(text[idx + 1..].chars().next(), Some('[' | '\\')) => {
+                if let Some((_, escaped)) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            '[' => {
+                let tag = text[idx + 1..]
+                    .find(']')
+                    .map(|len| &text[idx + 1..idx + 1 + len])
+                    .and_then(|tag| parse_tag(tag, theme).map(|parsed| (tag, parsed)));
+                let Some((tag, parsed)) = tag else {
+                    current.push(ch);
+                    continue;
+                };
+
+                push_text(&mut spans, &mut current, stack.last().unwrap_or(base));
+                match parsed {
+                    Tag::Open(style) => {
+                        let merged = stack.last().unwrap_or(base).clone().merge(&style);
+                        stack.push(merged);
+                    }
+                    Tag::Close => {
+                        // A stray `[/]` has nothing to close; the base stays.
+                        if stack.len() > 1 {
+                            stack.pop();
+                        }
+                    }
+                }
+                // Skip the tag and its closing bracket.
+                for _ in 0..tag.chars().count() + 1 {
+                    chars.next();
+                }
+            }
+            _ => current.push(ch),
+        }
+    }
+    push_text(&mut spans, &mut current, stack.last().unwrap_or(base));
+    spans
+}
+
+/// The text of `markup` with the tags removed, for output without styles.
+pub fn strip(markup: &str) -> String {
+    parse(markup, &Style::new(), &Theme::default_theme())
+        .iter()
+        .map(|span| span.text())
+        .collect()
+}
+
+/// Escapes `text` so that it shows up literally inside markup, e.g. a value
+/// typed by the user.
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('[', "\\[")
+}
+
+fn push_text(spans: &mut Vec<Span>, text: &mut String, style: &Style) {
+    if !text.is_empty() {
+        spans.push(
+            Span::new(std::mem::take(text))
+                .with_style(style.clone())
+                .with_wrap(Wrap::Word),
+        );
+    }
+}
+
+fn parse_tag(tag: &str, theme: &Theme) -> Option<Tag> {
+    // `[/]`, or `[/bold red]` naming what it closes for readability.
+    if let Some(closed) = tag.strip_prefix('/') {
+        return (closed.is_empty() || matches!(parse_tag(closed, theme), Some(Tag::Open(_))))
+            .then_some(Tag::Close);
+    }
+
+    parse_style(tag, theme).map(Tag::Open)
+}
+
+/// Reads the inside of an opening tag, like `bold red on black` or `hint`.
+/// Theme files describe their styles the same way.
+pub fn parse_style(spec: &str, theme: &Theme) -> Option<Style> {
+    let mut style = Style::new();
+    let mut words = spec.split_whitespace().peekable();
+    words.peek()?;
+    while let Some(word) = words.next() {
+        let word = word.to_ascii_lowercase();
+        style = if word == "on" {
+            style.with_background(Color::parse(words.next()?)?)
+        } else if let Some(named) = theme.style(&word) {
+            style.merge(named)
+        } else if let Some(color) = Color::parse(&word) {
+            style.with_color(color)
+        } else {
+            style.with_attribute(&word)?
+        };
+    }
+    Some(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(markup: &str) -> Vec<(String, Style)> {
+        parse(markup, &Style::new(), &Theme::default_theme())
+            .into_iter()
+            .map(|span| (span.text().to_string(), span.style().clone()))
+            .collect()
+    }
+
+    fn plain(text: &str) -> (String, Style) {
+        (text.to_string(), Style::new())
+    }
+
+    #[test]
+    fn brackets_that_are_not_tags_stay_literal() {
+        assert_eq!(parts("step [1/3]"), vec![plain("step [1/3]")]);
+        assert_eq!(parts("[color(300)]x"), vec![plain("[color(300)]x")]);
+        assert_eq!(parts("a [ b"), vec![plain("a [ b")]);
+    }
+
+    #[test]
+    fn escaped_brackets_are_text() {
+        assert_eq!(parts(r"\[bold]x\\"), vec![plain(r"[bold]x\")]);
+    }
+
+    #[test]
+    fn a_stray_close_is_dropped() {
+        assert_eq!(parts("a[/]b"), vec![plain("a"), plain("b")]);
+        assert_eq!(strip("[/]a[/]"), "a");
+    }
+
+    #[test]
+    fn unclosed_tags_run_to_the_end() {
+        let bold_red = Style::new().with_bold().with_color(Color::Red);
+        assert_eq!(
+            parts("a [bold red]b c"),
+            vec![plain("a "), ("b c".to_string(), bold_red)]
+        );
+    }
+
+    #[test]
+    fn tags_take_hex_colors_backgrounds_and_theme_styles() {
+        let theme = Theme::default_theme();
+        assert_eq!(
+            parts("[#ff8800]a[/][on blue]b[/][white on #000000]c[/][hint]d"),
+            vec![
+                (
+                    "a".to_string(),
+                    Style::new().with_color(Color::Rgb(255, 136, 0))
+                ),
+                ("b".to_string(), Style::new().with_background(Color::Blue)),
+                (
+                    "c".to_string(),
+                    Style::new().with_colors(Color::White, Color::Rgb(0, 0, 0))
+                ),
+                ("d".to_string(), theme.hint.clone()),
+            ]
+        );
+        assert_eq!(parts("[on]x"), vec![plain("[on]x")]);
+    }
+
+    #[test]
+    fn closing_restores_the_outer_style() {
+        let bold = Style::new().with_bold();
+        assert_eq!(
+            parts("[bold]a[red]b[/bold red]c[/]d"),
+            vec![
+                ("a".to_string(), bold.clone()),
+                ("b".to_string(), bold.clone().with_color(Color::Red)),
+                ("c".to_string(), bold),
+                plain("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_text_parses_back_to_itself() {
+        for text in [r"[bold]x[/]", r"a\[b", r"\", r"C:\path\[1]", "[1/3] [/]"] {
+            assert_eq!(strip(&escape(text)), text);
+        }
+    }
+}
@@ -1,7 +1,12 @@
+pub mod export;
 pub mod frame;
 pub mod layout;
+pub mod markup;
 pub mod node;
+pub mod render_context;
 pub mod renderer;
+pub mod sgr;
 pub mod span;
 pub mod style;
+pub mod symbols;
 pub mod theme;
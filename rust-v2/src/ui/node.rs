@@ -1,11 +1,26 @@
 use crate::input::Input;
-use crate::span::Span;
+use crate::layout::Container;
+use crate::markup;
+use crate::render_context::RenderContext;
+use crate::span::{HitTarget, Span};
+use crate::style::Style;
 use crate::theme::Theme;
-use unicode_width::UnicodeWidthStr;
 
 pub enum Node {
     Text(String),
     Input(Box<dyn Input>),
+    /// Children laid out one after another, without decoration of their own.
+    Group(Vec<Node>),
+    /// Children indented under a title.
+    Section {
+        title: String,
+        children: Vec<Node>,
+    },
+    /// Children inside a border, with an optional title in its top edge.
+    Boxed {
+        title: Option<String>,
+        children: Vec<Node>,
+    },
 }
 
 impl Node {
@@ -17,6 +32,43 @@ impl Node {
         Node::Input(Box::new(input))
     }
 
+    pub fn group(children: Vec<Node>) -> Self {
+        Node::Group(children)
+    }
+
+    pub fn section(title: impl Into<String>, children: Vec<Node>) -> Self {
+        Node::Section {
+            title: title.into(),
+            children,
+        }
+    }
+
+    pub fn boxed(title: Option<&str>, children: Vec<Node>) -> Self {
+        Node::Boxed {
+            title: title.map(str::to_string),
+            children,
+        }
+    }
+
+    /// Child nodes of a container; empty for text and inputs.
+    pub fn children(&self) -> &[Node] {
+        match self {
+            Node::Group(children)
+            | Node::Section { children, .. }
+            | Node::Boxed { children, .. } => children,
+            Node::Text(_) | Node::Input(_) => &[],
+        }
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Node] {
+        match self {
+            Node::Group(children)
+            | Node::Section { children, .. }
+            | Node::Boxed { children, .. } => children,
+            Node::Text(_) | Node::Input(_) => &mut [],
+        }
+    }
+
     pub fn as_input(&self) -> Option<&dyn Input> {
         match self {
             Node::Input(input) => Some(input.as_ref()),
@@ -31,68 +83,134 @@ impl Node {
         }
     }
 
-    pub fn render(&self, inline_error_message: bool, theme: &Theme) -> Vec<Span> {
+    pub fn render(&self, inline_error_message: bool, ctx: &RenderContext) -> Vec<Span> {
+        self.render_leaf(ctx, |input| {
+            Self::render_input(input, inline_error_message, ctx, true, false)
+        })
+    }
+
+    pub fn render_field(&self, inline_error_message: bool, ctx: &RenderContext) -> Vec<Span> {
+        self.render_leaf(ctx, |input| {
+            Self::render_input(input, inline_error_message, ctx, false, true)
+        })
+    }
+
+    /// The input without its label, bracketed only while focused.
+    pub fn render_value(&self, inline_error_message: bool, ctx: &RenderContext) -> Vec<Span> {
+        self.render_leaf(ctx, |input| {
+            Self::render_input(input, inline_error_message, ctx, false, false)
+        })
+    }
+
+    /// The decoration of a container, still without its children. `None`
+    /// for text and inputs.
+    pub fn container(&self, theme: &Theme) -> Option<Container> {
+        let title = |title: &str| markup::parse(title, &theme.prompt, theme);
         match self {
-            Node::Text(text) => vec![Span::new(text.clone())],
-            Node::Input(input) => Self::render_input(input.as_ref(), inline_error_message, theme, true, false),
+            Node::Group(_) => Some(Container::default()),
+            Node::Section { title: text, .. } => {
+                Some(Container::default().with_title(title(text)).with_indent(2))
+            }
+            Node::Boxed { title: text, .. } => {
+                let container = Container::default().with_border(theme.border.clone());
+                Some(match text {
+                    Some(text) => container.with_title(title(text)),
+                    None => container,
+                })
+            }
+            Node::Text(_) | Node::Input(_) => None,
         }
     }
 
-    pub fn render_field(&self, inline_error_message: bool, theme: &Theme) -> Vec<Span> {
+    fn render_leaf(
+        &self,
+        ctx: &RenderContext,
+        render_input: impl FnOnce(&dyn Input) -> Vec<Span>,
+    ) -> Vec<Span> {
         match self {
-            Node::Text(text) => vec![Span::new(text.clone())],
-            Node::Input(input) => Self::render_input(input.as_ref(), inline_error_message, theme, false, true),
+            Node::Text(text) => markup::parse(text, &Style::new(), ctx.theme),
+            Node::Input(input) => render_input(input.as_ref()),
+            // Containers are laid out as blocks, see `container`.
+            Node::Group(_) | Node::Section { .. } | Node::Boxed { .. } => Vec::new(),
         }
     }
 
     fn render_input(
         input: &dyn Input,
         inline_error_message: bool,
-        theme: &Theme,
+        ctx: &RenderContext,
         show_label: bool,
         always_brackets: bool,
     ) -> Vec<Span> {
+        let field = HitTarget::Field(input.id().clone());
+        let content = HitTarget::Content(input.id().clone());
+
+        let symbols = &ctx.theme.symbols;
         let mut spans = Vec::new();
         if show_label {
-            spans.push(Span::new(input.label()));
-            spans.push(Span::new(": "));
+            spans.push(Span::new(input.label()).with_target(field.clone()));
+            spans.push(Span::new(symbols.separator.as_str()).with_target(field.clone()));
         }
 
-        let content_spans = Self::content_spans(input, inline_error_message, theme);
-        let content_width: usize = content_spans.iter().map(|s| s.text().width()).sum();
+        let content_spans: Vec<Span> = Self::content_spans(input, inline_error_message, ctx)
+            .into_iter()
+            .map(|span| span.with_target(content.clone()))
+            .collect();
+        let content_width: usize = content_spans.iter().map(|s| s.width()).sum();
+        // While an error message stands in for the value there is no
+        // position in it for the cursor to point at.
+        let content_spans =
+            if input.is_focused() && !shows_error_message(input, inline_error_message) {
+                with_cursor_anchor(content_spans, input.cursor_offset_in_content())
+            } else {
+                content_spans
+            };
         let use_brackets = always_brackets || input.is_focused();
+        // Padding up to the minimum width stops where the line does, so the
+        // closing bracket stays on it.
+        let label_width: usize = spans.iter().map(|s| s.width()).sum();
+        let open = Span::new(symbols.open_bracket.as_str()).with_target(field.clone());
+        let close = Span::new(symbols.close_bracket.as_str()).with_target(field);
+        let bracket_width = open.width() + close.width();
+        let min_width = input
+            .min_width()
+            .min(ctx.width.saturating_sub(label_width + bracket_width));
 
         if use_brackets {
-            spans.push(Span::new("["));
+            spans.push(open);
         }
 
         spans.extend(content_spans);
 
-        if use_brackets && content_width < input.min_width() {
-            let padding = input.min_width() - content_width;
-            spans.push(Span::new(" ".repeat(padding)));
+        if use_brackets && content_width < min_width {
+            let padding = min_width - content_width;
+            spans.push(Span::new(" ".repeat(padding)).with_target(content));
         }
 
         if use_brackets {
-            spans.push(Span::new("]"));
+            spans.push(close);
         }
 
         spans
     }
 
-    fn content_spans(input: &dyn Input, inline_error_message: bool, theme: &Theme) -> Vec<Span> {
-        let error_style = theme.error.clone();
+    fn content_spans(
+        input: &dyn Input,
+        inline_error_message: bool,
+        ctx: &RenderContext,
+    ) -> Vec<Span> {
+        let error_style = ctx.theme.error.clone();
 
-        if inline_error_message {
-            if let Some(err) = input.error() {
-                return vec![
-                    Span::new("✗ ").with_style(error_style.clone()),
-                    Span::new(err).with_style(error_style.clone()),
-                ];
-            }
+        if shows_error_message(input, inline_error_message)
+            && let Some(err) = input.error()
+        {
+            return vec![
+                Span::new(format!("{} ", ctx.theme.symbols.error)).with_style(error_style.clone()),
+                Span::new(err).with_style(error_style.clone()),
+            ];
         }
 
-        let mut spans = input.render_content();
+        let mut spans = input.render_content(ctx);
         if input.error().is_some() {
             spans = spans
                 .into_iter()
@@ -104,27 +222,154 @@ impl Node {
         }
         spans
     }
+}
 
-    pub fn cursor_offset(&self) -> Option<usize> {
-        match self {
-            Node::Input(input) if input.is_focused() => {
-                let label_len = input.label().width() + 2;
-                let bracket_len = 1;
-                let content_offset = input.cursor_offset_in_content();
-                Some(label_len + bracket_len + content_offset)
-            }
-            _ => None,
+/// Every input under `nodes`, depth first. This is also the focus order.
+pub fn inputs(nodes: &[Node]) -> Vec<&dyn Input> {
+    let mut result = Vec::new();
+    for node in nodes {
+        match node {
+            Node::Input(input) => result.push(input.as_ref()),
+            _ => result.extend(inputs(node.children())),
         }
     }
+    result
+}
 
-    pub fn cursor_offset_in_field(&self) -> Option<usize> {
-        match self {
-            Node::Input(input) if input.is_focused() => {
-                let bracket_len = 1;
-                let content_offset = input.cursor_offset_in_content();
-                Some(bracket_len + content_offset)
-            }
-            _ => None,
+pub fn inputs_mut(nodes: &mut [Node]) -> Vec<&mut dyn Input> {
+    let mut result = Vec::new();
+    for node in nodes {
+        if matches!(node, Node::Input(_)) {
+            result.extend(node.as_input_mut());
+        } else {
+            result.extend(inputs_mut(node.children_mut()));
         }
     }
+    result
+}
+
+/// Child index paths to every input under `nodes`, in the order of [`inputs`].
+pub fn input_paths(nodes: &[Node]) -> Vec<Vec<usize>> {
+    let mut paths = Vec::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        if node.as_input().is_some() {
+            paths.push(vec![idx]);
+            continue;
+        }
+        for mut path in input_paths(node.children()) {
+            path.insert(0, idx);
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+pub fn node_at<'a>(nodes: &'a [Node], path: &[usize]) -> Option<&'a Node> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.get(*first)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        node_at(node.children(), rest)
+    }
+}
+
+pub fn node_at_mut<'a>(nodes: &'a mut [Node], path: &[usize]) -> Option<&'a mut Node> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.get_mut(*first)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        node_at_mut(node.children_mut(), rest)
+    }
+}
+
+fn shows_error_message(input: &dyn Input, inline_error_message: bool) -> bool {
+    inline_error_message && input.error().is_some()
+}
+
+/// Inserts a cursor anchor `offset` columns into `spans`, or after them when
+/// they are shorter.
+fn with_cursor_anchor(spans: Vec<Span>, offset: usize) -> Vec<Span> {
+    let mut result = Vec::with_capacity(spans.len() + 2);
+    let mut col = 0;
+    let mut placed = false;
+    for span in spans {
+        let width = span.width();
+        if !placed && offset < col + width {
+            let (head, tail) = span.split_at_width(offset - col);
+            result.push(head);
+            result.push(Span::cursor_anchor());
+            result.extend(tail);
+            placed = true;
+        } else {
+            result.push(span);
+        }
+        col += width;
+    }
+    if !placed {
+        result.push(Span::cursor_anchor());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_input::TextInput;
+    use crate::theme::Theme;
+
+    fn focused_input(error: Option<&str>) -> Node {
+        let mut input = TextInput::new("name", "Name");
+        input.set_value("bob".to_string());
+        input.set_focused(true);
+        input.set_error(error.map(str::to_string));
+        Node::input(input)
+    }
+
+    fn anchor_count(spans: &[Span]) -> usize {
+        spans.iter().filter(|span| span.is_cursor_anchor()).count()
+    }
+
+    #[test]
+    fn container_titles_are_markup() {
+        let nodes = [
+            Node::section("Profile [hint](optional)[/]", vec![Node::text("x")]),
+            Node::boxed(Some("[bold]Account[/]"), vec![Node::text("y")]),
+        ];
+        let theme = Theme::default_theme();
+        let frame = crate::layout::Layout::new().compose(&nodes, &RenderContext::new(&theme, 20));
+        assert_eq!(
+            frame.to_plain(),
+            "Profile (optional)\n  x\n┌─ Account ────────┐\n│ y                │\n└──────────────────┘"
+        );
+    }
+
+    #[test]
+    fn min_width_padding_stops_at_the_available_width() {
+        let mut input = TextInput::new("name", "Name").with_min_width(20);
+        input.set_value("bob".to_string());
+        input.set_focused(true);
+        let theme = Theme::default_theme();
+        let spans = Node::input(input).render_value(true, &RenderContext::new(&theme, 10));
+        let text: String = spans.iter().map(|span| span.text()).collect();
+        assert_eq!(text, "[bob     ]");
+    }
+
+    #[test]
+    fn a_focused_value_carries_the_cursor_anchor() {
+        let theme = Theme::default_theme();
+        let ctx = RenderContext::new(&theme, 40);
+        assert_eq!(anchor_count(&focused_input(None).render(true, &ctx)), 1);
+    }
+
+    #[test]
+    fn an_inline_error_message_has_no_cursor_anchor() {
+        let theme = Theme::default_theme();
+        let ctx = RenderContext::new(&theme, 40);
+        let spans = focused_input(Some("Too short")).render(true, &ctx);
+        assert_eq!(anchor_count(&spans), 0);
+        let text: String = spans.iter().map(|span| span.text()).collect();
+        assert!(text.contains("Too short"));
+    }
 }
@@ -0,0 +1,20 @@
+use crate::theme::Theme;
+
+/// What an input gets to know about where it is drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderContext<'a> {
+    pub theme: &'a Theme,
+    /// Columns available to the content. Longer content wraps, and
+    /// padding up to an input's minimum width stops here.
+    pub width: usize,
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn new(theme: &'a Theme, width: usize) -> Self {
+        Self { theme, width }
+    }
+
+    pub fn with_width(self, width: usize) -> Self {
+        Self { width, ..self }
+    }
+}
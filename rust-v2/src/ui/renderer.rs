@@ -1,129 +1,339 @@
-use crate::layout::Layout;
-use crate::node::Node;
 use crate::form_step::FormStep;
-use crate::terminal::Terminal;
+use crate::frame::{Frame, Hit, Line};
+use crate::layout::{Block, Column, Container, Layout, Row};
+use crate::markup;
+use crate::node::Node;
+use crate::render_context::RenderContext;
+use crate::span::{HitTarget, Span};
+use crate::terminal::{Backend, Size};
 use crate::theme::Theme;
 use crate::view_state::{ErrorDisplay, ViewState};
-use std::io::{self, Write};
+use std::io;
 use unicode_width::UnicodeWidthStr;
 
-struct RenderLine {
-    spans: Vec<crate::span::Span>,
-    cursor_offset: Option<usize>,
+/// Narrowest a value gets beside its label. Below that the label moves to
+/// its own line above the value.
+const MIN_VALUE_WIDTH: usize = 10;
+
+/// Where a step is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Below the shell prompt, at the current cursor row.
+    #[default]
+    Inline,
+    /// On the alternate screen, using the whole terminal. The shell contents
+    /// come back once the step (or the program) is done.
+    FullScreen,
 }
 
 pub struct Renderer {
+    mode: RenderMode,
+    alternate_screen: bool,
+    /// Inline anchor and line count, kept while a step is on the alternate screen.
+    inline_state: Option<(Option<u16>, usize)>,
     start_row: Option<u16>,
     num_lines: usize,
+    previous: Option<Frame>,
+    previous_size: Size,
+    previous_cursor: Option<(u16, u16)>,
+    scroll_offset: usize,
+    /// Whether the viewport scrolls to keep the focused input in view. Off
+    /// after the user scrolled by hand.
+    follow_focus: bool,
 }
 
 impl Renderer {
     pub fn new() -> Self {
         Self {
+            mode: RenderMode::Inline,
+            alternate_screen: false,
+            inline_state: None,
             start_row: None,
             num_lines: 0,
+            previous: None,
+            previous_size: Size {
+                width: 0,
+                height: 0,
+            },
+            previous_cursor: None,
+            scroll_offset: 0,
+            follow_focus: true,
         }
     }
 
+    pub fn with_mode(mut self, mode: RenderMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        self.mode = mode;
+    }
+
+    /// Scrolls a form taller than the screen by `delta` lines. The view stays
+    /// there, even with the focused input out of sight, until
+    /// `follow_focus` is called.
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta);
+        self.follow_focus = false;
+    }
+
+    /// Brings the focused input back into view on the next render and keeps
+    /// it there.
+    pub fn follow_focus(&mut self) {
+        self.follow_focus = true;
+    }
+
+    /// Forgets what is on screen so the next render repaints every line.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
     pub fn render(
         &mut self,
         step: &FormStep,
         view_state: &ViewState,
         theme: &Theme,
-        terminal: &mut Terminal,
+        terminal: &mut dyn Backend,
     ) -> io::Result<()> {
         let _ = terminal.refresh_size()?;
-        let width = terminal.size().width;
-        let render_lines = self.build_render_lines(step, view_state, theme);
-        let frame = Layout::new().compose_spans(
-            render_lines.iter().map(|line| line.spans.clone()),
-            width,
-        );
+        self.switch_mode(terminal, step.render_mode.unwrap_or(self.mode))?;
+
+        let size = terminal.size();
+        let width = size.width;
+        if size != self.previous_size {
+            self.reanchor(terminal, width)?;
+            self.invalidate();
+            self.previous_size = size;
+        }
+
+        let frame = self.compose(step, view_state, theme, width);
+
+        let height = terminal.size().height as usize;
+        let cursor_pos = frame.cursor();
+        let focus_line = cursor_pos
+            .map(|(_, line_idx)| line_idx)
+            .filter(|_| self.follow_focus);
+        let viewport = Viewport::fit(frame.lines().len(), height, self.scroll_offset, focus_line);
+        self.scroll_offset = viewport.offset;
+        let frame = viewport.apply(frame, theme);
+
         let lines = frame.lines();
         let start = self.ensure_start_row(terminal, lines.len())?;
+        let cursor = cursor_pos.and_then(|(col, line_idx)| {
+            viewport
+                .screen_row(line_idx)
+                .map(|row| (col as u16, start + row as u16))
+        });
+
+        let previous = self.previous.take();
+        let unchanged = previous.as_ref() == Some(&frame);
+        if unchanged && cursor == self.previous_cursor {
+            self.previous = previous;
+            return terminal.flush();
+        }
+
+        // The whole frame, cursor included, reaches the terminal in one
+        // write and, where supported, is shown in one go.
+        terminal.queue_begin_synchronized_update()?;
         terminal.queue_hide_cursor()?;
-        self.draw_lines(terminal, start, lines)?;
-        self.clear_extra_lines(terminal, start, lines.len())?;
-        self.num_lines = lines.len();
-        terminal.flush()?;
+        if !unchanged {
+            self.draw_lines(terminal, start, lines, previous.as_ref())?;
+            self.clear_extra_lines(terminal, start, lines.len())?;
+            self.num_lines = lines.len();
+        }
 
-        let cursor_pos = self.find_cursor_position(&render_lines);
-        if let Some((col, line_idx)) = cursor_pos {
-            let cursor_row = start + line_idx as u16;
-            terminal.queue_move_cursor(col as u16, cursor_row)?;
+        // With nothing focused the cursor stays hidden, parked on the first
+        // row so that `reanchor` always knows where it was left.
+        let (col, row) = cursor.unwrap_or((0, start));
+        terminal.queue_move_cursor(col, row)?;
+        if cursor.is_some() {
+            terminal.queue_show_cursor()?;
         }
-        terminal.queue_show_cursor()?;
+        terminal.queue_end_synchronized_update()?;
         terminal.flush()?;
 
+        self.previous = Some(frame);
+        self.previous_cursor = cursor;
         Ok(())
     }
 
-    fn find_cursor_position(&self, render_lines: &[RenderLine]) -> Option<(usize, usize)> {
-        let mut line_idx = 0;
+    fn switch_mode(&mut self, terminal: &mut dyn Backend, mode: RenderMode) -> io::Result<()> {
+        let full_screen = mode == RenderMode::FullScreen;
+        if full_screen == self.alternate_screen {
+            return Ok(());
+        }
 
-        for line in render_lines {
-            if let Some(offset) = line.cursor_offset {
-                return Some((offset, line_idx));
-            }
+        if full_screen {
+            self.inline_state = Some((self.start_row, self.num_lines));
+            terminal.queue_enter_alternate_screen()?;
+            terminal.queue_move_cursor(0, 0)?;
+            terminal.queue_clear_from_cursor_down()?;
+            self.start_row = Some(0);
+            self.num_lines = 0;
+        } else {
+            terminal.queue_leave_alternate_screen()?;
+            let (start_row, num_lines) = self.inline_state.take().unwrap_or((None, 0));
+            self.start_row = start_row;
+            self.num_lines = num_lines;
+        }
 
-            let newlines = line.spans.iter().filter(|s| s.text() == "\n").count();
-            line_idx += 1 + newlines;
+        self.alternate_screen = full_screen;
+        self.scroll_offset = 0;
+        self.previous_cursor = None;
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Starts over after the process was suspended: whatever was on screen
+    /// before is gone or elsewhere, so the next render draws a fresh form at
+    /// the cursor (or at the top of the alternate screen).
+    pub fn reset(&mut self, terminal: &mut dyn Backend) -> io::Result<()> {
+        self.previous = None;
+        self.previous_cursor = None;
+        self.num_lines = 0;
+        if self.alternate_screen {
+            terminal.queue_move_cursor(0, 0)?;
+            terminal.queue_clear_from_cursor_down()?;
+            self.start_row = Some(0);
+        } else {
+            self.start_row = None;
         }
+        Ok(())
+    }
 
-        None
+    /// Finds the input drawn at screen position (`x`, `y`) by the last render.
+    pub fn hit_test(&self, x: u16, y: u16) -> Option<Hit> {
+        let line = y.checked_sub(self.start_row?)?;
+        self.previous.as_ref()?.hit_test(x as usize, line as usize)
     }
 
-    pub fn move_to_end(&self, terminal: &mut Terminal) -> io::Result<()> {
+    /// Puts the cursor below the form, leaving the alternate screen first
+    /// when a full-screen step is still showing.
+    pub fn move_to_end(&mut self, terminal: &mut dyn Backend) -> io::Result<()> {
+        self.switch_mode(terminal, RenderMode::Inline)?;
         if let Some(start) = self.start_row {
-            let end_row = start + self.num_lines as u16;
-            terminal.queue_move_cursor(0, end_row)?;
+            let end_row = start as usize + self.num_lines;
+            let last_row = terminal.size().height.saturating_sub(1);
+            if end_row > last_row as usize {
+                terminal.queue_move_cursor(0, last_row)?;
+                terminal.queue_newline()?;
+            } else {
+                terminal.queue_move_cursor(0, end_row as u16)?;
+            }
             terminal.flush()?;
         }
         Ok(())
     }
 
-    fn ensure_start_row(&mut self, terminal: &mut Terminal, line_count: usize) -> io::Result<u16> {
-        if let Some(start) = self.start_row {
-            return Ok(start);
+    /// Finds the form again after a resize and wipes the rows it occupied.
+    ///
+    /// Terminals that reflow their contents re-wrap every line wider than the
+    /// new `width` (and join them back when widening), which moves the
+    /// hardware cursor. Counting the physical rows each previously drawn line
+    /// takes at the new width gives the distance from the cursor back up to
+    /// the first row of the form.
+    fn reanchor(&mut self, terminal: &mut dyn Backend, width: u16) -> io::Result<()> {
+        if self.alternate_screen {
+            terminal.queue_move_cursor(0, 0)?;
+            return terminal.queue_clear_from_cursor_down();
         }
 
+        let (Some(start), Some(previous)) = (self.start_row, self.previous.as_ref()) else {
+            return Ok(());
+        };
+
+        let width = (width as usize).max(1);
+        let (col, row) = self.previous_cursor.unwrap_or((0, start));
+        let cursor_line = row.saturating_sub(start) as usize;
+        let rows_above: usize = previous
+            .lines()
+            .iter()
+            .take(cursor_line)
+            .map(|line| line.width().div_ceil(width).max(1))
+            .sum();
+
         terminal.refresh_cursor_position()?;
-        let pos = terminal.cursor_position();
-        terminal.queue_move_cursor(0, pos.y)?;
-        {
-            let out = terminal.writer_mut();
-            for _ in 0..line_count {
-                writeln!(out)?;
+        let cursor_row = terminal.cursor_position().y as usize;
+        let start = cursor_row.saturating_sub(rows_above + col as usize / width) as u16;
+
+        terminal.queue_move_cursor(0, start)?;
+        terminal.queue_clear_from_cursor_down()?;
+        self.start_row = Some(start);
+        self.num_lines = 0;
+        Ok(())
+    }
+
+    /// Returns the first row of the form, scrolling the terminal up when the
+    /// form needs more rows below it than are left on screen. Everything
+    /// already drawn scrolls together with the anchor, so earlier output
+    /// ends up in the scrollback instead of being overwritten.
+    fn ensure_start_row(
+        &mut self,
+        terminal: &mut dyn Backend,
+        line_count: usize,
+    ) -> io::Result<u16> {
+        let start = match self.start_row {
+            Some(start) => start,
+            None => {
+                terminal.refresh_cursor_position()?;
+                let start = terminal.cursor_position().y;
+                self.num_lines = line_count;
+                start
+            }
+        };
+
+        let height = terminal.size().height as usize;
+        let overflow = (start as usize + line_count).saturating_sub(height);
+        if overflow > 0 {
+            terminal.queue_move_cursor(0, height.saturating_sub(1) as u16)?;
+            for _ in 0..overflow {
+                terminal.queue_newline()?;
             }
         }
-        terminal.flush()?;
 
-        terminal.refresh_cursor_position()?;
-        let pos = terminal.cursor_position();
-        let start = pos.y.saturating_sub(line_count as u16);
+        let start = start.saturating_sub(overflow as u16);
         self.start_row = Some(start);
-        self.num_lines = line_count;
         Ok(start)
     }
 
+    /// Draws `lines`, skipping whatever already matches `previous` and
+    /// redrawing changed lines only from their first differing cell.
     fn draw_lines(
         &self,
-        terminal: &mut Terminal,
+        terminal: &mut dyn Backend,
         start: u16,
-        lines: &[crate::frame::Line],
+        lines: &[Line],
+        previous: Option<&Frame>,
     ) -> io::Result<()> {
         for (idx, line) in lines.iter().enumerate() {
             let line_row = start + idx as u16;
-            terminal.queue_move_cursor(0, line_row)?;
-            terminal.queue_clear_line()?;
-            terminal.render_line(line)?;
+            let Some(old) = previous.and_then(|frame| frame.lines().get(idx)) else {
+                terminal.queue_move_cursor(0, line_row)?;
+                terminal.queue_clear_line()?;
+                terminal.render_line(line)?;
+                continue;
+            };
+
+            let Some(col) = old.diff_column(line) else {
+                continue;
+            };
+            terminal.queue_move_cursor(col as u16, line_row)?;
+            terminal.render_line(&line.slice_from(col))?;
+            if old.width() > line.width() {
+                terminal.queue_clear_until_newline()?;
+            }
         }
         Ok(())
     }
 
     fn clear_extra_lines(
         &self,
-        terminal: &mut Terminal,
+        terminal: &mut dyn Backend,
         start: u16,
         current_len: usize,
     ) -> io::Result<()> {
@@ -139,33 +349,44 @@ impl Renderer {
         Ok(())
     }
 
-    fn build_render_lines(&self, step: &FormStep, view_state: &ViewState, theme: &Theme) -> Vec<RenderLine> {
-        let mut lines = Vec::new();
+    /// Lays `step` out at `width` columns without drawing it. The frame
+    /// covers the whole step; nothing is cut to the terminal height.
+    fn compose(&self, step: &FormStep, view_state: &ViewState, theme: &Theme, width: u16) -> Frame {
+        let ctx = RenderContext::new(theme, width as usize);
+        Layout::new().compose_blocks(self.build_blocks(step, view_state, &ctx), width)
+    }
+
+    fn build_blocks(
+        &self,
+        step: &FormStep,
+        view_state: &ViewState,
+        ctx: &RenderContext,
+    ) -> Vec<Block> {
+        let mut blocks = Vec::new();
 
         let inline_prompt_input = self.inline_prompt_input(step);
 
-        if let Some(line) = self.render_prompt_line(step, inline_prompt_input, view_state, theme) {
-            lines.push(line);
+        if let Some(block) = self.render_prompt_line(step, inline_prompt_input, view_state, ctx) {
+            blocks.push(block);
         }
 
-        if !(inline_prompt_input.is_some() && !step.prompt.is_empty()) {
-            lines.extend(self.render_nodes(step, view_state, theme));
+        if inline_prompt_input.is_none() || step.prompt.is_empty() {
+            blocks.extend(self.render_nodes(&step.nodes, view_state, ctx));
         }
 
-        if let Some(line) = self.render_hint_line(step, theme) {
-            lines.push(line);
+        if let Some(block) = self.render_hint_line(step, ctx.theme) {
+            blocks.push(block);
         }
 
-        lines
+        blocks
     }
 
     fn inline_prompt_input<'a>(&self, step: &'a FormStep) -> Option<&'a Node> {
-        if step.nodes.len() == 1 {
-            if let Some(node) = step.nodes.first() {
-                if matches!(node, crate::node::Node::Input(_)) {
-                    return Some(node);
-                }
-            }
+        if step.nodes.len() == 1
+            && let Some(node) = step.nodes.first()
+            && matches!(node, crate::node::Node::Input(_))
+        {
+            return Some(node);
         }
         None
     }
@@ -175,12 +396,13 @@ impl Renderer {
         step: &FormStep,
         inline_prompt_input: Option<&crate::node::Node>,
         view_state: &ViewState,
-        theme: &Theme,
-    ) -> Option<RenderLine> {
+        ctx: &RenderContext,
+    ) -> Option<Block> {
         if step.prompt.is_empty() {
             return None;
         }
 
+        let theme = ctx.theme;
         let prompt_style = theme.prompt.clone();
         if let Some(node) = inline_prompt_input {
             let inline_error = match node.as_input() {
@@ -190,55 +412,457 @@ impl Renderer {
                 ),
                 None => false,
             };
-            let mut spans = vec![
-                crate::span::Span::new(step.prompt.clone()).with_style(prompt_style),
-                crate::span::Span::new(" "),
-            ];
-            spans.extend(node.render_field(inline_error, theme));
-            let prompt_width = step.prompt.width();
-            let cursor_offset = node
-                .cursor_offset_in_field()
-                .map(|offset| offset + prompt_width + 1);
-            Some(RenderLine { spans, cursor_offset })
+            let mut spans = markup::parse(&step.prompt, &prompt_style, theme);
+            spans.push(Span::new(" "));
+            spans.extend(node.render_field(inline_error, ctx));
+            Some(Block::Spans(spans))
         } else {
-            Some(RenderLine {
-                spans: vec![crate::span::Span::new(step.prompt.clone()).with_style(prompt_style)],
-                cursor_offset: None,
-            })
+            Some(Block::Spans(markup::parse(
+                &step.prompt,
+                &prompt_style,
+                theme,
+            )))
         }
     }
 
     fn render_nodes(
         &self,
-        step: &FormStep,
+        nodes: &[Node],
         view_state: &ViewState,
-        theme: &Theme,
-    ) -> Vec<RenderLine> {
-        step.nodes
+        ctx: &RenderContext,
+    ) -> Vec<Block> {
+        // Every field starts in the same column, right after the widest label
+        // among its siblings.
+        let label_column = nodes
+            .iter()
+            .filter_map(|node| node.as_input())
+            .map(|input| input.label().width() + ctx.theme.symbols.separator.width())
+            .max()
+            .unwrap_or(0);
+
+        nodes
             .iter()
             .map(|node| {
-                let inline_error = match node.as_input() {
-                    Some(input) => matches!(
-                        view_state.error_display(input.id()),
-                        ErrorDisplay::InlineMessage
-                    ),
-                    None => false,
+                if let Some(container) = node.container(ctx.theme) {
+                    let inner = ctx.with_width(container.inner_width(ctx.width));
+                    let children = self.render_nodes(node.children(), view_state, &inner);
+                    return Block::Container(container.with_children(children));
+                }
+                let Some(input) = node.as_input() else {
+                    return Block::Spans(node.render(false, ctx));
                 };
-                let spans = node.render(inline_error, theme);
-                let cursor_offset = node.cursor_offset();
-                RenderLine { spans, cursor_offset }
+
+                let inline_error = matches!(
+                    view_state.error_display(input.id()),
+                    ErrorDisplay::InlineMessage
+                );
+                let field = HitTarget::Field(input.id().clone());
+                let label = vec![
+                    Span::new(input.label()).with_target(field.clone()),
+                    Span::new(ctx.theme.symbols.separator.as_str()).with_target(field),
+                ];
+                let label_width: usize = label.iter().map(Span::width).sum();
+                if label_width + MIN_VALUE_WIDTH > ctx.width {
+                    // Too narrow to share a line: the label goes above the value.
+                    return Block::Container(Container::new(vec![
+                        Block::Spans(label),
+                        Block::Spans(node.render_value(inline_error, ctx)),
+                    ]));
+                }
+
+                // The label column gives up its padding before the value
+                // drops below its minimum.
+                let value_width = ctx.width.saturating_sub(label_column).max(MIN_VALUE_WIDTH);
+                let value_ctx = ctx.with_width(value_width);
+                let row = Row::new(vec![
+                    Column::fixed(label, label_column),
+                    Column::flex(node.render_value(inline_error, &value_ctx))
+                        .with_min_width(MIN_VALUE_WIDTH),
+                ])
+                .with_gap(0);
+                Block::Row(row)
             })
             .collect()
     }
 
-    fn render_hint_line(&self, step: &FormStep, theme: &Theme) -> Option<RenderLine> {
+    fn render_hint_line(&self, step: &FormStep, theme: &Theme) -> Option<Block> {
         let hint = step.hint.as_ref()?;
         if hint.is_empty() {
             return None;
         }
-        Some(RenderLine {
-            spans: vec![crate::span::Span::new(hint.clone()).with_style(theme.hint.clone())],
-            cursor_offset: None,
-        })
+        Some(Block::Spans(markup::parse(hint, &theme.hint, theme)))
+    }
+}
+
+/// Renders `step` into a frame the way it would look at `width` columns, for
+/// snapshots and previews. Serialise it with `Frame::to_plain`,
+/// `Frame::to_ansi`, `Frame::to_html` or `Frame::to_svg`.
+pub fn render_to_frame(
+    step: &FormStep,
+    view_state: &ViewState,
+    theme: &Theme,
+    width: u16,
+) -> Frame {
+    Renderer::new().compose(step, view_state, theme, width)
+}
+
+/// The slice of a frame that fits on screen, plus the rows taken by the
+/// "more above" / "more below" indicators.
+#[derive(Debug, Clone, Copy)]
+struct Viewport {
+    offset: usize,
+    body: usize,
+    total: usize,
+    above: bool,
+    below: bool,
+}
+
+impl Viewport {
+    /// Picks a scroll offset close to `offset` that keeps `focus` visible.
+    fn fit(total: usize, height: usize, offset: usize, focus: Option<usize>) -> Self {
+        if total <= height || height < 3 {
+            let body = total.min(height);
+            return Self {
+                offset: 0,
+                body,
+                total,
+                above: false,
+                below: false,
+            };
+        }
+
+        let body_at = |offset: usize| {
+            let rest = height - usize::from(offset > 0);
+            if offset + rest >= total {
+                total - offset
+            } else {
+                rest - 1
+            }
+        };
+
+        // Never leave empty rows at the bottom once the frame shrinks.
+        let mut offset = offset.min(total - (height - 1));
+        // A focus left over from a longer frame points past the end.
+        if let Some(focus) = focus.map(|focus| focus.min(total - 1)) {
+            if focus < offset {
+                offset = focus;
+            }
+            while focus >= offset + body_at(offset) {
+                offset += 1;
+            }
+        }
+
+        let body = body_at(offset);
+        Self {
+            offset,
+            body,
+            total,
+            above: offset > 0,
+            below: offset + body < total,
+        }
+    }
+
+    fn screen_row(&self, line_idx: usize) -> Option<usize> {
+        if line_idx < self.offset || line_idx >= self.offset + self.body {
+            return None;
+        }
+        Some(line_idx - self.offset + usize::from(self.above))
+    }
+
+    fn apply(&self, frame: Frame, theme: &Theme) -> Frame {
+        if !self.above && !self.below && self.body == self.total {
+            return frame;
+        }
+
+        let mut visible = Frame::new();
+        let lines = visible.lines_mut();
+        if self.above {
+            lines.push(Self::indicator(
+                format!("↑ {} more above", self.offset),
+                theme,
+            ));
+        }
+        lines.extend(
+            frame
+                .lines()
+                .iter()
+                .skip(self.offset)
+                .take(self.body)
+                .cloned(),
+        );
+        if self.below {
+            let hidden = self.total - self.offset - self.body;
+            lines.push(Self::indicator(format!("↓ {} more below", hidden), theme));
+        }
+        visible
+    }
+
+    fn indicator(text: String, theme: &Theme) -> Line {
+        let mut line = Line::new();
+        line.push(Span::new(text).with_style(theme.hint.clone()));
+        line
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+    use crate::style::ColorSupport;
+    use crate::terminal::{Pos, TestBackend};
+    use crate::text_input::TextInput;
+
+    fn sign_in_step() -> FormStep {
+        let mut name = TextInput::new("name", "Name");
+        name.set_value("bob".to_string());
+        name.set_focused(true);
+        FormStep {
+            prompt: "Sign in".to_string(),
+            hint: Some("Enter to submit".to_string()),
+            nodes: vec![
+                Node::input(name),
+                Node::input(TextInput::new("email", "Email")),
+            ],
+            render_mode: None,
+        }
+    }
+
+    #[test]
+    fn golden_plain_frame() {
+        let frame = render_to_frame(
+            &sign_in_step(),
+            &ViewState::new(),
+            &Theme::default_theme(),
+            30,
+        );
+        assert_eq!(
+            frame.to_plain(),
+            "Sign in\nName:  [bob]\nEmail:\nEnter to submit"
+        );
+    }
+
+    #[test]
+    fn golden_ansi_frame() {
+        let frame = render_to_frame(
+            &sign_in_step(),
+            &ViewState::new(),
+            &Theme::default_theme(),
+            30,
+        );
+        assert_eq!(
+            frame.to_ansi(ColorSupport::Ansi256),
+            "\x1b[1mSign in\x1b[0m\nName:  [bob]\nEmail: \n\x1b[90mEnter to submit\x1b[0m"
+        );
+        // Without color support only the attributes are left.
+        assert_eq!(
+            frame.to_ansi(ColorSupport::None),
+            "\x1b[1mSign in\x1b[0m\nName:  [bob]\nEmail: \nEnter to submit"
+        );
+    }
+
+    #[test]
+    fn resizing_redraws_the_form_where_it_was() {
+        let theme = Theme::default_theme();
+        let view_state = ViewState::new();
+        let mut backend = TestBackend::new(30, 10);
+        let mut shell = Line::new();
+        shell.push(Span::new("$ ask"));
+        backend.render_line(&shell).unwrap();
+        backend.queue_move_cursor(0, 1).unwrap();
+
+        let step = sign_in_step();
+        let mut renderer = Renderer::new();
+        renderer
+            .render(&step, &view_state, &theme, &mut backend)
+            .unwrap();
+        let wide = "$ ask\nSign in\nName:  [bob]\nEmail:\nEnter to submit";
+        assert_eq!(backend.contents(), wide);
+
+        backend.resize(12, 10);
+        renderer
+            .render(&step, &view_state, &theme, &mut backend)
+            .unwrap();
+        assert_eq!(
+            backend.contents(),
+            "$ ask\nSign in\nName:\n[bob]\nEmail:\nEnter to\nsubmit"
+        );
+        assert_eq!(backend.cursor_position(), Pos { x: 4, y: 3 });
+
+        backend.resize(30, 10);
+        renderer
+            .render(&step, &view_state, &theme, &mut backend)
+            .unwrap();
+        assert_eq!(backend.contents(), wide);
+        assert_eq!(backend.cursor_position(), Pos { x: 11, y: 2 });
+    }
+
+    #[test]
+    fn full_screen_steps_use_the_alternate_screen() {
+        let theme = Theme::default_theme();
+        let view_state = ViewState::new();
+        let mut backend = TestBackend::new(30, 10);
+        let mut shell = Line::new();
+        shell.push(Span::new("$ ask"));
+        backend.render_line(&shell).unwrap();
+        backend.queue_move_cursor(0, 1).unwrap();
+
+        let inline = sign_in_step();
+        let mut full_screen = sign_in_step();
+        full_screen.render_mode = Some(RenderMode::FullScreen);
+        let mut renderer = Renderer::new();
+
+        renderer
+            .render(&inline, &view_state, &theme, &mut backend)
+            .unwrap();
+        renderer
+            .render(&full_screen, &view_state, &theme, &mut backend)
+            .unwrap();
+        assert!(backend.is_alternate_screen());
+        assert_eq!(
+            backend.contents(),
+            "Sign in\nName:  [bob]\nEmail:\nEnter to submit"
+        );
+
+        renderer
+            .render(&inline, &view_state, &theme, &mut backend)
+            .unwrap();
+        assert!(!backend.is_alternate_screen());
+        let shown = "$ ask\nSign in\nName:  [bob]\nEmail:\nEnter to submit";
+        assert_eq!(backend.contents(), shown);
+
+        // Finishing on a full-screen step goes back to the shell's screen too.
+        renderer
+            .render(&full_screen, &view_state, &theme, &mut backend)
+            .unwrap();
+        renderer.move_to_end(&mut backend).unwrap();
+        assert!(!backend.is_alternate_screen());
+        assert_eq!(backend.contents(), shown);
+        assert_eq!(backend.cursor_position(), Pos { x: 0, y: 5 });
+    }
+
+    fn meeting_step(focused: bool) -> FormStep {
+        let mut name = TextInput::new("name", "Name");
+        name.set_value("bob".to_string());
+        let mut meeting = TextInput::new("meeting", "Meeting Time");
+        meeting.set_value("hello".to_string());
+        meeting.set_focused(focused);
+        FormStep {
+            prompt: String::new(),
+            hint: None,
+            nodes: vec![Node::input(name), Node::input(meeting)],
+            render_mode: None,
+        }
+    }
+
+    fn render_at(step: &FormStep, width: u16) -> TestBackend {
+        let mut backend = TestBackend::new(width, 6);
+        Renderer::new()
+            .render(
+                step,
+                &ViewState::new(),
+                &Theme::default_theme(),
+                &mut backend,
+            )
+            .unwrap();
+        backend
+    }
+
+    #[test]
+    fn narrow_rows_shrink_the_label_column_before_the_value() {
+        // "Meeting Time: " is 14 wide; at 22 the value keeps its minimum of
+        // 10 and the shorter "Name: " loses its padding instead.
+        let backend = render_at(&meeting_step(false), 22);
+        assert_eq!(backend.contents(), "Name:       bob\nMeeting Time:\nhello");
+        let backend = render_at(&meeting_step(false), 24);
+        assert_eq!(backend.contents(), "Name:         bob\nMeeting Time: hello");
+    }
+
+    #[test]
+    fn labels_move_above_values_that_would_not_fit_beside_them() {
+        let cases = [
+            (8, "Name:\nbob\nMeeting\nTime:\n[hello]"),
+            (14, "Name:\nbob\nMeeting Time:\n[hello]"),
+            (16, "Name: bob\nMeeting Time:\n[hello]"),
+        ];
+        for (width, expected) in cases {
+            assert_eq!(
+                render_at(&meeting_step(true), width).contents(),
+                expected,
+                "width {width}"
+            );
+        }
+    }
+
+    #[test]
+    fn the_cursor_stays_on_screen_at_any_width() {
+        for width in 4..=16 {
+            let step = meeting_step(true);
+            let frame = render_to_frame(&step, &ViewState::new(), &Theme::default_theme(), width);
+            let (col, _) = frame.cursor().unwrap();
+            assert!(col < width as usize, "frame cursor {col} at width {width}");
+
+            let backend = render_at(&step, width);
+            assert!(backend.cursor_position().x < width, "width {width}");
+            assert!(backend.is_cursor_visible());
+        }
+    }
+
+    fn tall_step() -> FormStep {
+        let nodes = (0..10)
+            .map(|idx| {
+                let mut input = TextInput::new(format!("f{idx}"), format!("Field {idx}"));
+                input.set_focused(idx == 0);
+                Node::input(input)
+            })
+            .collect();
+        FormStep {
+            prompt: String::new(),
+            hint: None,
+            nodes,
+            render_mode: None,
+        }
+    }
+
+    #[test]
+    fn scrolling_by_hand_leaves_the_focus_behind_until_followed() {
+        let step = tall_step();
+        let theme = Theme::default_theme();
+        let view_state = ViewState::new();
+        let mut backend = TestBackend::new(30, 5);
+        let mut renderer = Renderer::new();
+
+        renderer.scroll_by(3);
+        renderer
+            .render(&step, &view_state, &theme, &mut backend)
+            .unwrap();
+        assert_eq!(backend.row_text(0), "↑ 3 more above");
+        assert!(!backend.is_cursor_visible());
+
+        renderer.follow_focus();
+        renderer
+            .render(&step, &view_state, &theme, &mut backend)
+            .unwrap();
+        assert!(backend.row_text(0).starts_with("Field 0"));
+        assert!(backend.is_cursor_visible());
+    }
+
+    #[test]
+    fn viewport_scrolls_to_the_focus() {
+        let viewport = Viewport::fit(20, 5, 0, Some(10));
+        assert_eq!(viewport.screen_row(10), Some(3));
+        assert!(viewport.above && viewport.below);
+    }
+
+    #[test]
+    fn viewport_clamps_a_focus_past_the_end() {
+        let viewport = Viewport::fit(20, 5, 0, Some(25));
+        assert_eq!(viewport.offset + viewport.body, 20);
+        assert!(!viewport.below);
     }
 }
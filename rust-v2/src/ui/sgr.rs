@@ -0,0 +1,190 @@
+use crate::style::{Color, Style};
+use crossterm::style::{Attribute, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use std::io::{self, Write};
+
+/// Remembers which SGR attributes are active so that consecutive spans only
+/// emit what actually changes between them.
+#[derive(Debug, Default)]
+pub struct SgrState {
+    current: Style,
+}
+
+impl SgrState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn transition(&mut self, out: &mut impl Write, target: &Style) -> io::Result<()> {
+        if *target == self.current {
+            return Ok(());
+        }
+        if target.is_plain() {
+            return self.reset(out);
+        }
+
+        let from = &self.current;
+        let mut bold = from.bold();
+        let mut dim = from.dim();
+        // Bold and dim share a single "normal intensity" reset.
+        if (bold && !target.bold()) || (dim && !target.dim()) {
+            write!(out, "{}", SetAttribute(Attribute::NormalIntensity))?;
+            bold = false;
+            dim = false;
+        }
+        if target.bold() && !bold {
+            write!(out, "{}", SetAttribute(Attribute::Bold))?;
+        }
+        if target.dim() && !dim {
+            write!(out, "{}", SetAttribute(Attribute::Dim))?;
+        }
+
+        let toggles = [
+            (
+                from.italic(),
+                target.italic(),
+                Attribute::Italic,
+                Attribute::NoItalic,
+            ),
+            (
+                from.underline(),
+                target.underline(),
+                Attribute::Underlined,
+                Attribute::NoUnderline,
+            ),
+            (
+                from.blink(),
+                target.blink(),
+                Attribute::SlowBlink,
+                Attribute::NoBlink,
+            ),
+            (
+                from.reverse(),
+                target.reverse(),
+                Attribute::Reverse,
+                Attribute::NoReverse,
+            ),
+            (
+                from.strikethrough(),
+                target.strikethrough(),
+                Attribute::CrossedOut,
+                Attribute::NotCrossedOut,
+            ),
+        ];
+        for (was, is, on, off) in toggles {
+            if was != is {
+                write!(out, "{}", SetAttribute(if is { on } else { off }))?;
+            }
+        }
+
+        if from.color() != target.color() {
+            match target.color() {
+                Some(color) => write!(out, "\x1b[{}m", color_sgr(color, false))?,
+                None => write!(
+                    out,
+                    "{}",
+                    SetForegroundColor(crossterm::style::Color::Reset)
+                )?,
+            }
+        }
+        if from.background() != target.background() {
+            match target.background() {
+                Some(color) => write!(out, "\x1b[{}m", color_sgr(color, true))?,
+                None => write!(
+                    out,
+                    "{}",
+                    SetBackgroundColor(crossterm::style::Color::Reset)
+                )?,
+            }
+        }
+
+        self.current = target.clone();
+        Ok(())
+    }
+
+    /// Returns to the terminal's default attributes, if not there already.
+    pub fn reset(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if !self.current.is_plain() {
+            write!(out, "{}", SetAttribute(Attribute::Reset))?;
+            self.current = Style::default();
+        }
+        Ok(())
+    }
+}
+
+/// The SGR parameters selecting `color`. Named colors use the 16-color
+/// codes (30-37 and 90-97, or 40-47 and 100-107 for backgrounds), so they
+/// also work on terminals that only know those; palette and RGB colors use
+/// the extended forms.
+fn color_sgr(color: Color, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+    let named = |offset: u8| (base + offset).to_string();
+    let bright = |offset: u8| (base + 60 + offset).to_string();
+    let extended = if background { 48 } else { 38 };
+    match color {
+        Color::Black => named(0),
+        Color::DarkGrey => bright(0),
+        Color::Red => bright(1),
+        Color::Green => bright(2),
+        Color::Yellow => bright(3),
+        Color::Blue => bright(4),
+        Color::Magenta => bright(5),
+        Color::Cyan => bright(6),
+        Color::White => bright(7),
+        Color::Ansi(index) => format!("{extended};5;{index}"),
+        Color::Rgb(r, g, b) => format!("{extended};2;{r};{g};{b}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+    use crate::span::Span;
+    use crate::style::ColorSupport;
+
+    fn transitions(styles: &[Style]) -> String {
+        let mut out = Vec::new();
+        let mut sgr = SgrState::new();
+        for style in styles {
+            sgr.transition(&mut out, style).unwrap();
+            out.push(b'|');
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn colors_use_the_shortest_form_the_color_allows() {
+        let on = |color: Color| Style::new().with_colors(color, color);
+        assert_eq!(transitions(&[on(Color::Black)]), "\x1b[30m\x1b[40m|");
+        assert_eq!(transitions(&[on(Color::White)]), "\x1b[97m\x1b[107m|");
+        assert_eq!(
+            transitions(&[on(Color::Ansi(208))]),
+            "\x1b[38;5;208m\x1b[48;5;208m|"
+        );
+        assert_eq!(
+            transitions(&[on(Color::Rgb(1, 2, 3))]),
+            "\x1b[38;2;1;2;3m\x1b[48;2;1;2;3m|"
+        );
+    }
+
+    #[test]
+    fn sixteen_color_terminals_get_sixteen_color_codes() {
+        let mut frame = Frame::new();
+        frame.ensure_line();
+        let red = Style::new().with_color(Color::Rgb(200, 10, 10));
+        frame
+            .current_line_mut()
+            .push(Span::new("x").with_style(red));
+        assert_eq!(frame.to_ansi(ColorSupport::Ansi16), "\x1b[91mx\x1b[0m");
+    }
+
+    #[test]
+    fn only_changes_are_written() {
+        let bold = Style::new().with_bold();
+        let bold_red = Style::new().with_bold().with_color(Color::Red);
+        assert_eq!(
+            transitions(&[bold.clone(), bold_red, bold, Style::new()]),
+            "\x1b[1m|\x1b[91m|\x1b[39m|\x1b[0m|"
+        );
+    }
+}
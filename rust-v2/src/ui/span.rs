@@ -1,11 +1,33 @@
+use crate::input::NodeId;
 use crate::style::Style;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum Wrap {
+    /// Break at the exact column where the line is full.
     #[default]
     Yes,
     No,
+    /// Break at whitespace; only words wider than the line are split.
+    Word,
+}
+
+/// The input a span was drawn for, so mouse clicks can be mapped back to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HitTarget {
+    /// Label, brackets and other decoration around an input.
+    Field(NodeId),
+    /// The input's own content; clicks report a column within it.
+    Content(NodeId),
+}
+
+impl HitTarget {
+    pub fn id(&self) -> &NodeId {
+        match self {
+            HitTarget::Field(id) | HitTarget::Content(id) => id,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -13,6 +35,8 @@ pub struct Span {
     text: String,
     style: Style,
     wrap: Wrap,
+    cursor_anchor: bool,
+    target: Option<HitTarget>,
 }
 
 impl Span {
@@ -21,9 +45,24 @@ impl Span {
             text: text.into(),
             style: Style::default(),
             wrap: Wrap::Yes,
+            cursor_anchor: false,
+            target: None,
+        }
+    }
+
+    /// Zero-width marker for where the terminal cursor belongs. `Layout`
+    /// records the row and column it ends up at after wrapping.
+    pub fn cursor_anchor() -> Self {
+        Self {
+            cursor_anchor: true,
+            ..Self::new("")
         }
     }
 
+    pub fn is_cursor_anchor(&self) -> bool {
+        self.cursor_anchor
+    }
+
     pub fn text(&self) -> &str {
         &self.text
     }
@@ -36,6 +75,10 @@ impl Span {
         self.wrap
     }
 
+    pub fn target(&self) -> Option<&HitTarget> {
+        self.target.as_ref()
+    }
+
     pub fn with_style(mut self, style: Style) -> Self {
         self.style = style;
         self
@@ -46,8 +89,13 @@ impl Span {
         self
     }
 
+    pub fn with_target(mut self, target: HitTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
     pub fn width(&self) -> usize {
-        self.text.width()
+        self.text.graphemes(true).map(|g| g.width()).sum()
     }
 
     pub fn split_at_width(&self, max: usize) -> (Span, Option<Span>) {
@@ -60,16 +108,17 @@ impl Span {
             return (self.clone(), None);
         }
 
-        // Znajdź punkt podziału uwzględniając szerokość unicode
+        // Znajdź punkt podziału uwzględniając szerokość unicode; całe klastry
+        // grafemów (emoji ZWJ, flagi, znaki z akcentami) nigdy nie są dzielone
         let mut current_width = 0;
         let mut split_idx = 0;
-        for (idx, ch) in self.text.char_indices() {
-            let char_width = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
-            if current_width + char_width > max {
+        for (idx, grapheme) in self.text.grapheme_indices(true) {
+            let grapheme_width = grapheme.width();
+            if current_width + grapheme_width > max {
                 break;
             }
-            current_width += char_width;
-            split_idx = idx + ch.len_utf8();
+            current_width += grapheme_width;
+            split_idx = idx + grapheme.len();
         }
 
         let (left, right) = self.text.split_at(split_idx);
@@ -88,14 +137,18 @@ impl Span {
             text: String::new(),
             style: self.style.clone(),
             wrap: self.wrap,
+            cursor_anchor: false,
+            target: self.target.clone(),
         }
     }
 
-    fn clone_with_text(&self, text: &str) -> Span {
+    pub(crate) fn clone_with_text(&self, text: &str) -> Span {
         Span {
             text: text.to_string(),
             style: self.style.clone(),
             wrap: self.wrap,
+            cursor_anchor: false,
+            target: self.target.clone(),
         }
     }
 }
@@ -9,17 +9,218 @@ pub enum Color {
     Magenta,
     Cyan,
     White,
+    /// Index into the 256-color palette.
+    Ansi(u8),
+    Rgb(u8, u8, u8),
 }
 
+/// How many colors the terminal can display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Reads `NO_COLOR`, `COLORTERM` and `TERM` from the environment.
+    pub fn detect() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        Self::from_env_values(no_color, &colorterm, &term)
+    }
+
+    pub fn from_env_values(no_color: bool, colorterm: &str, term: &str) -> Self {
+        if no_color || term == "dumb" {
+            return ColorSupport::None;
+        }
+        let colorterm = colorterm.to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+            return ColorSupport::TrueColor;
+        }
+        if term.contains("256color") {
+            return ColorSupport::Ansi256;
+        }
+        ColorSupport::Ansi16
+    }
+}
+
+impl Color {
+    /// Reads a color name (`red`, `dark_grey`, ...), `#rrggbb`, or
+    /// `color(n)` for an entry of the 256-color palette.
+    pub fn parse(text: &str) -> Option<Color> {
+        let text = text.trim().to_ascii_lowercase();
+        if let Some(hex) = text.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if let Some(index) = text
+            .strip_prefix("color(")
+            .and_then(|t| t.strip_suffix(')'))
+        {
+            return index.parse().ok().map(Color::Ansi);
+        }
+        let color = match text.as_str() {
+            "black" => Color::Black,
+            "darkgrey" | "dark_grey" | "darkgray" | "dark_gray" | "grey" | "gray" => {
+                Color::DarkGrey
+            }
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "white" => Color::White,
+            _ => return None,
+        };
+        Some(color)
+    }
+
+    /// Returns the closest color the terminal can show, or `None` when it
+    /// should not get any color at all.
+    pub fn downsample(self, support: ColorSupport) -> Option<Color> {
+        match (support, self) {
+            (ColorSupport::None, _) => None,
+            (ColorSupport::TrueColor, color) => Some(color),
+            (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => {
+                Some(Color::Ansi(rgb_to_ansi256(r, g, b)))
+            }
+            (ColorSupport::Ansi256, color) => Some(color),
+            (ColorSupport::Ansi16, Color::Ansi(index)) if index < 16 => {
+                Some(ansi16_to_named(index))
+            }
+            (ColorSupport::Ansi16, Color::Ansi(index)) => {
+                let (r, g, b) = ansi256_to_rgb(index);
+                Some(rgb_to_named(r, g, b))
+            }
+            (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => Some(rgb_to_named(r, g, b)),
+            (ColorSupport::Ansi16, color) => Some(color),
+        }
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_index = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (**level as i32 - v as i32).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = ansi256_to_rgb(cube);
+
+    // The grayscale ramp is often closer for desaturated colors.
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (avg.saturating_sub(8) / 10).min(23) as u8;
+    let gray = 232 + gray_step;
+    let gray_rgb = ansi256_to_rgb(gray);
 
+    if distance((r, g, b), gray_rgb) < distance((r, g, b), cube_rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => named_rgb(ansi16_to_named(index)),
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+fn ansi16_to_named(index: u8) -> Color {
+    match index {
+        0 => Color::Black,
+        8 => Color::DarkGrey,
+        1 | 9 => Color::Red,
+        2 | 10 => Color::Green,
+        3 | 11 => Color::Yellow,
+        4 | 12 => Color::Blue,
+        5 | 13 => Color::Magenta,
+        6 | 14 => Color::Cyan,
+        _ => Color::White,
+    }
+}
+
+const NAMED: [Color; 9] = [
+    Color::Black,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+/// Red, green and blue components of any color. Named and palette colors
+/// use the same reference values as `Color::downsample`.
+pub(crate) fn named_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Black => (0, 0, 0),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::Blue => (0, 0, 255),
+        Color::Magenta => (255, 0, 255),
+        Color::Cyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        Color::Ansi(index) => ansi256_to_rgb(index),
+        Color::Rgb(r, g, b) => (r, g, b),
+    }
+}
+
+fn rgb_to_named(r: u8, g: u8, b: u8) -> Color {
+    NAMED
+        .iter()
+        .copied()
+        .min_by_key(|named| distance((r, g, b), named_rgb(*named)))
+        .unwrap_or(Color::White)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Style {
     color: Option<Color>,
     background: Option<Color>,
     bold: bool,
+    dim: bool,
     italic: bool,
     underline: bool,
+    blink: bool,
+    reverse: bool,
+    strikethrough: bool,
 }
 
 impl Style {
@@ -39,6 +240,10 @@ impl Style {
         self.bold
     }
 
+    pub fn dim(&self) -> bool {
+        self.dim
+    }
+
     pub fn italic(&self) -> bool {
         self.italic
     }
@@ -47,6 +252,22 @@ impl Style {
         self.underline
     }
 
+    pub fn blink(&self) -> bool {
+        self.blink
+    }
+
+    pub fn reverse(&self) -> bool {
+        self.reverse
+    }
+
+    pub fn strikethrough(&self) -> bool {
+        self.strikethrough
+    }
+
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+
     pub fn with_color(mut self, color: Color) -> Self {
         self.color = Some(color);
         self
@@ -68,6 +289,11 @@ impl Style {
         self
     }
 
+    pub fn with_dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
     pub fn with_italic(mut self) -> Self {
         self.italic = true;
         self
@@ -78,6 +304,37 @@ impl Style {
         self
     }
 
+    pub fn with_blink(mut self) -> Self {
+        self.blink = true;
+        self
+    }
+
+    pub fn with_reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn with_strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+
+    /// Turns on the attribute called `name` (`bold`, `italic`, ...).
+    /// Returns `None` for an unknown name.
+    pub fn with_attribute(self, name: &str) -> Option<Self> {
+        let style = match name {
+            "bold" => self.with_bold(),
+            "dim" => self.with_dim(),
+            "italic" => self.with_italic(),
+            "underline" => self.with_underline(),
+            "blink" => self.with_blink(),
+            "reverse" => self.with_reverse(),
+            "strikethrough" => self.with_strikethrough(),
+            _ => return None,
+        };
+        Some(style)
+    }
+
     pub fn merge(mut self, other: &Style) -> Self {
         if other.color.is_some() {
             self.color = other.color;
@@ -88,12 +345,114 @@ impl Style {
         if other.bold {
             self.bold = true;
         }
+        if other.dim {
+            self.dim = true;
+        }
         if other.italic {
             self.italic = true;
         }
         if other.underline {
             self.underline = true;
         }
+        if other.blink {
+            self.blink = true;
+        }
+        if other.reverse {
+            self.reverse = true;
+        }
+        if other.strikethrough {
+            self.strikethrough = true;
+        }
         self
     }
+
+    /// Same style with its colors reduced to what `support` can show.
+    pub fn downsample(&self, support: ColorSupport) -> Style {
+        Style {
+            color: self.color.and_then(|c| c.downsample(support)),
+            background: self.background.and_then(|c| c.downsample(support)),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_color_and_dumb_terminals_get_no_color() {
+        assert_eq!(
+            ColorSupport::from_env_values(true, "truecolor", "xterm-256color"),
+            ColorSupport::None
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "truecolor", "dumb"),
+            ColorSupport::None
+        );
+    }
+
+    #[test]
+    fn colorterm_and_term_pick_the_palette() {
+        assert_eq!(
+            ColorSupport::from_env_values(false, "truecolor", "xterm"),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "24BIT", "xterm"),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "", "xterm-direct"),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "", "xterm-256color"),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "", "screen-256color"),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "", "xterm"),
+            ColorSupport::Ansi16
+        );
+        assert_eq!(
+            ColorSupport::from_env_values(false, "", ""),
+            ColorSupport::Ansi16
+        );
+    }
+
+    #[test]
+    fn greys_use_the_ramp_and_hues_use_the_cube() {
+        assert_eq!(
+            Color::Rgb(128, 128, 128).downsample(ColorSupport::Ansi256),
+            Some(Color::Ansi(244))
+        );
+        assert_eq!(
+            Color::Rgb(8, 8, 8).downsample(ColorSupport::Ansi256),
+            Some(Color::Ansi(232))
+        );
+        assert_eq!(
+            Color::Rgb(255, 0, 0).downsample(ColorSupport::Ansi256),
+            Some(Color::Ansi(196))
+        );
+        assert_eq!(
+            Color::Rgb(95, 135, 175).downsample(ColorSupport::Ansi256),
+            Some(Color::Ansi(67))
+        );
+    }
+
+    #[test]
+    fn rgb_and_palette_colors_fall_back_to_the_closest_named_color() {
+        let ansi16 = |color: Color| color.downsample(ColorSupport::Ansi16);
+        assert_eq!(ansi16(Color::Rgb(200, 10, 10)), Some(Color::Red));
+        assert_eq!(ansi16(Color::Rgb(10, 10, 200)), Some(Color::Blue));
+        assert_eq!(ansi16(Color::Rgb(100, 100, 100)), Some(Color::DarkGrey));
+        assert_eq!(ansi16(Color::Ansi(9)), Some(Color::Red));
+        assert_eq!(ansi16(Color::Ansi(196)), Some(Color::Red));
+        assert_eq!(ansi16(Color::Cyan), Some(Color::Cyan));
+        assert_eq!(Color::Cyan.downsample(ColorSupport::None), None);
+    }
 }
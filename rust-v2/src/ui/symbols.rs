@@ -0,0 +1,67 @@
+/// The characters drawn around and next to input values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbols {
+    pub open_bracket: String,
+    pub close_bracket: String,
+    /// Between a label and its value.
+    pub separator: String,
+    /// Marks a failed validation.
+    pub error: String,
+}
+
+impl Symbols {
+    /// The full set, for UTF-8 terminals.
+    pub fn unicode() -> Self {
+        Self {
+            open_bracket: "[".to_string(),
+            close_bracket: "]".to_string(),
+            separator: ": ".to_string(),
+            error: "✗".to_string(),
+        }
+    }
+
+    /// Plain ASCII, for serial consoles and terminals without the glyphs.
+    pub fn ascii() -> Self {
+        Self {
+            open_bracket: "[".to_string(),
+            close_bracket: "]".to_string(),
+            separator: ": ".to_string(),
+            error: "x".to_string(),
+        }
+    }
+
+    /// Unicode when the locale is UTF-8 and the terminal can draw it,
+    /// ASCII otherwise. Reads `LC_ALL`, `LC_CTYPE`, `LANG` and `TERM`.
+    pub fn detect() -> Self {
+        // The first locale variable that is set wins, as in setlocale(3).
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        Self::from_env_values(&locale, &term)
+    }
+
+    pub fn from_env_values(locale: &str, term: &str) -> Self {
+        if Self::supports_unicode(locale, term) {
+            Self::unicode()
+        } else {
+            Self::ascii()
+        }
+    }
+
+    fn supports_unicode(locale: &str, term: &str) -> bool {
+        let locale = locale.to_ascii_lowercase();
+        let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+        // The Linux console's font and serial terminals lack most of the glyphs.
+        let limited_term = term == "dumb" || term == "linux" || term.starts_with("vt");
+        utf8 && !limited_term
+    }
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Self::unicode()
+    }
+}
@@ -1,4 +1,35 @@
+//! Styles and symbols used to draw a form.
+//!
+//! A theme is one of the built-in presets or is loaded from a TOML or YAML
+//! file. Styles in a file are written like markup tags:
+//!
+//! ```toml
+//! extends = "light-background"
+//! prompt = "bold blue"
+//! error = "bold white on red"
+//!
+//! [symbols]
+//! set = "ascii"
+//! error = "x"
+//! ```
+//!
+//! Every field is optional and falls back to the preset named by `extends`,
+//! or to the default theme. Symbols default to Unicode, or to ASCII when
+//! [`Theme::from_env`] finds a terminal that can't draw it; `set` picks one
+//! of the two explicitly.
+
+use crate::markup;
 use crate::style::{Color, Style};
+use crate::symbols::Symbols;
+use serde::Deserialize;
+use std::io;
+use std::path::Path;
+
+/// Environment variable holding a preset name or the path of a theme file.
+pub const THEME_ENV: &str = "RUSTICAL_THEME";
+
+/// Names accepted by [`Theme::preset`].
+pub const PRESETS: &[&str] = &["default", "high-contrast", "monochrome", "light-background"];
 
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -7,6 +38,14 @@ pub struct Theme {
     pub error: Style,
     pub placeholder: Style,
     pub focused: Style,
+    /// The highlighted choice in a list.
+    pub selected: Style,
+    /// Inputs and choices that can't be changed.
+    pub disabled: Style,
+    pub success: Style,
+    pub warning: Style,
+    pub border: Style,
+    pub symbols: Symbols,
 }
 
 impl Theme {
@@ -14,31 +53,357 @@ impl Theme {
         Self {
             prompt: Style::new().with_bold(),
             hint: Style::new().with_color(Color::DarkGrey),
+            error: Style::new().with_color(Color::Red).with_bold(),
+            placeholder: Style::new().with_color(Color::DarkGrey),
+            focused: Style::new().with_bold(),
+            selected: Style::new().with_color(Color::Cyan).with_bold(),
+            disabled: Style::new()
+                .with_color(Color::DarkGrey)
+                .with_strikethrough(),
+            success: Style::new().with_color(Color::Green),
+            warning: Style::new().with_color(Color::Yellow),
+            border: Style::new().with_color(Color::DarkGrey),
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Bright text and no grey, for low vision and washed-out displays.
+    pub fn high_contrast() -> Self {
+        Self {
+            prompt: Style::new().with_color(Color::White).with_bold(),
+            hint: Style::new().with_color(Color::White),
             error: Style::new()
-                .with_color(Color::Red)
+                .with_colors(Color::White, Color::Red)
                 .with_bold(),
-            placeholder: Style::new().with_color(Color::DarkGrey),
+            placeholder: Style::new().with_color(Color::White).with_italic(),
+            focused: Style::new().with_bold().with_underline(),
+            selected: Style::new().with_bold().with_reverse(),
+            disabled: Style::new().with_color(Color::White).with_strikethrough(),
+            success: Style::new().with_color(Color::Green).with_bold(),
+            warning: Style::new().with_color(Color::Yellow).with_bold(),
+            border: Style::new().with_color(Color::White),
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Attributes only, for terminals without color.
+    pub fn monochrome() -> Self {
+        Self {
+            prompt: Style::new().with_bold(),
+            hint: Style::new().with_dim(),
+            error: Style::new().with_bold().with_underline(),
+            placeholder: Style::new().with_dim(),
             focused: Style::new().with_bold(),
+            selected: Style::new().with_reverse(),
+            disabled: Style::new().with_dim().with_strikethrough(),
+            success: Style::new().with_bold(),
+            warning: Style::new().with_bold(),
+            border: Style::new(),
+            symbols: Symbols::default(),
+        }
+    }
+
+    /// Darker colors that stay readable on a white or pale background.
+    pub fn light_background() -> Self {
+        Self {
+            prompt: Style::new().with_color(Color::Blue).with_bold(),
+            hint: Style::new().with_color(Color::Ansi(244)),
+            error: Style::new().with_color(Color::Ansi(124)).with_bold(),
+            placeholder: Style::new().with_color(Color::Ansi(246)),
+            focused: Style::new().with_bold(),
+            selected: Style::new().with_color(Color::Blue).with_bold(),
+            disabled: Style::new()
+                .with_color(Color::Ansi(246))
+                .with_strikethrough(),
+            success: Style::new().with_color(Color::Ansi(28)),
+            warning: Style::new().with_color(Color::Ansi(130)),
+            border: Style::new().with_color(Color::Ansi(246)),
+            symbols: Symbols::default(),
+        }
+    }
+
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// One of the [`PRESETS`] by name.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "default" => Some(Self::default_theme()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "monochrome" => Some(Self::monochrome()),
+            "light-background" | "light" => Some(Self::light_background()),
+            _ => None,
+        }
+    }
+
+    /// The theme named by [`THEME_ENV`], either a preset or a file path.
+    /// Unset or empty means the default theme. Its symbols fall back to
+    /// ASCII on terminals that can't draw Unicode, see [`Symbols::detect`].
+    pub fn from_env() -> io::Result<Self> {
+        // The only place the locale and TERM are read; everything else
+        // starts from the Unicode set.
+        let symbols = Symbols::detect();
+        match std::env::var(THEME_ENV) {
+            Ok(value) if !value.trim().is_empty() => Self::resolve(&value, symbols),
+            _ => Ok(Self::default_theme().with_symbols(symbols)),
+        }
+    }
+
+    pub fn from_name_or_path(value: &str) -> io::Result<Self> {
+        Self::resolve(value, Symbols::default())
+    }
+
+    /// Reads a theme file, picking the format from its extension.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_file(path.as_ref(), Symbols::default())
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        ThemeFile::from_toml(text)?.into_theme(Symbols::default())
+    }
+
+    pub fn from_yaml(text: &str) -> io::Result<Self> {
+        ThemeFile::from_yaml(text)?.into_theme(Symbols::default())
+    }
+
+    /// A preset or theme file, with `symbols` unless the file sets its own.
+    fn resolve(value: &str, symbols: Symbols) -> io::Result<Self> {
+        match Self::preset(value) {
+            Some(theme) => Ok(theme.with_symbols(symbols)),
+            None => Self::read_file(Path::new(value), symbols),
+        }
+    }
+
+    fn read_file(path: &Path, symbols: Symbols) -> io::Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        let parse = match extension.as_deref() {
+            Some("toml") => ThemeFile::from_toml,
+            Some("yaml" | "yml") => ThemeFile::from_yaml,
+            _ if !path.exists() => {
+                return Err(invalid_input(format!(
+                    "unknown theme '{}' (presets: {})",
+                    path.display(),
+                    PRESETS.join(", ")
+                )));
+            }
+            _ => {
+                return Err(invalid_input(format!(
+                    "theme file '{}' should end in .toml, .yaml or .yml",
+                    path.display()
+                )));
+            }
+        };
+        let text = std::fs::read_to_string(path)?;
+        parse(&text)
+            .and_then(|file| file.into_theme(symbols))
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    }
+
+    /// Looks a style up by its field name, as used in markup like `[hint]`.
+    pub fn style(&self, name: &str) -> Option<&Style> {
+        match name {
+            "prompt" => Some(&self.prompt),
+            "hint" => Some(&self.hint),
+            "error" => Some(&self.error),
+            "placeholder" => Some(&self.placeholder),
+            "focused" => Some(&self.focused),
+            "selected" => Some(&self.selected),
+            "disabled" => Some(&self.disabled),
+            "success" => Some(&self.success),
+            "warning" => Some(&self.warning),
+            "border" => Some(&self.border),
+            _ => None,
+        }
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        match name {
+            "prompt" => Some(&mut self.prompt),
+            "hint" => Some(&mut self.hint),
+            "error" => Some(&mut self.error),
+            "placeholder" => Some(&mut self.placeholder),
+            "focused" => Some(&mut self.focused),
+            "selected" => Some(&mut self.selected),
+            "disabled" => Some(&mut self.disabled),
+            "success" => Some(&mut self.success),
+            "warning" => Some(&mut self.warning),
+            "border" => Some(&mut self.border),
+            _ => None,
         }
     }
 }
 
-pub fn prompt_style() -> Style {
-    Theme::default_theme().prompt
+impl Default for Theme {
+    fn default() -> Self {
+        Self::default_theme()
+    }
 }
 
-pub fn hint_style() -> Style {
-    Theme::default_theme().hint
+/// A theme file as written; everything left out comes from `extends`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    extends: Option<String>,
+    prompt: Option<String>,
+    hint: Option<String>,
+    error: Option<String>,
+    placeholder: Option<String>,
+    focused: Option<String>,
+    selected: Option<String>,
+    disabled: Option<String>,
+    success: Option<String>,
+    warning: Option<String>,
+    border: Option<String>,
+    #[serde(default)]
+    symbols: SymbolsFile,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SymbolsFile {
+    set: Option<String>,
+    open_bracket: Option<String>,
+    close_bracket: Option<String>,
+    separator: Option<String>,
+    error: Option<String>,
+}
+
+impl ThemeFile {
+    fn from_toml(text: &str) -> io::Result<Self> {
+        toml::from_str(text).map_err(invalid_data)
+    }
+
+    fn from_yaml(text: &str) -> io::Result<Self> {
+        serde_norway::from_str(text).map_err(invalid_data)
+    }
+
+    /// `symbols` stand in for the preset's until `set` picks a set.
+    fn into_theme(self, symbols: Symbols) -> io::Result<Theme> {
+        let mut theme = match &self.extends {
+            Some(name) => Theme::preset(name).ok_or_else(|| {
+                invalid_data(format!(
+                    "unknown preset '{}' in extends (presets: {})",
+                    name,
+                    PRESETS.join(", ")
+                ))
+            })?,
+            None => Theme::default_theme(),
+        }
+        .with_symbols(symbols);
+
+        // Specs may name other styles (`error = "warning underline"`); those
+        // refer to the base theme, so the order of fields doesn't matter.
+        let base = theme.clone();
+        let styles = [
+            ("prompt", self.prompt),
+            ("hint", self.hint),
+            ("error", self.error),
+            ("placeholder", self.placeholder),
+            ("focused", self.focused),
+            ("selected", self.selected),
+            ("disabled", self.disabled),
+            ("success", self.success),
+            ("warning", self.warning),
+            ("border", self.border),
+        ];
+        for (name, spec) in styles {
+            let Some(spec) = spec else { continue };
+            let style = if spec.trim().is_empty() {
+                Style::new()
+            } else {
+                markup::parse_style(&spec, &base)
+                    .ok_or_else(|| invalid_data(format!("invalid style '{}' for {}", spec, name)))?
+            };
+            if let Some(slot) = theme.style_mut(name) {
+                *slot = style;
+            }
+        }
+
+        let symbols = self.symbols;
+        if let Some(set) = &symbols.set {
+            theme.symbols = match set.trim().to_ascii_lowercase().as_str() {
+                "unicode" => Symbols::unicode(),
+                "ascii" => Symbols::ascii(),
+                _ => {
+                    return Err(invalid_data(format!(
+                        "unknown symbol set '{}' (expected unicode or ascii)",
+                        set
+                    )));
+                }
+            };
+        }
+        let fields = [
+            (&mut theme.symbols.open_bracket, symbols.open_bracket),
+            (&mut theme.symbols.close_bracket, symbols.close_bracket),
+            (&mut theme.symbols.separator, symbols.separator),
+            (&mut theme.symbols.error, symbols.error),
+        ];
+        for (slot, value) in fields {
+            if let Some(value) = value {
+                *slot = value;
+            }
+        }
+
+        Ok(theme)
+    }
 }
 
-pub fn error_style() -> Style {
-    Theme::default_theme().error
+fn invalid_data(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
 }
 
-pub fn placeholder_style() -> Style {
-    Theme::default_theme().placeholder
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
 }
 
-pub fn focused_style() -> Style {
-    Theme::default_theme().focused
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_and_toml_files_read_the_same() {
+        let yaml =
+            Theme::from_yaml("extends: monochrome\nerror: bold red\nsymbols:\n  set: ascii\n")
+                .unwrap();
+        let toml = Theme::from_toml(
+            "extends = \"monochrome\"\nerror = \"bold red\"\n[symbols]\nset = \"ascii\"\n",
+        )
+        .unwrap();
+        for theme in [yaml, toml] {
+            assert_eq!(theme.error, Style::new().with_bold().with_color(Color::Red));
+            assert_eq!(theme.hint, Theme::monochrome().hint);
+            assert_eq!(theme.symbols, Symbols::ascii());
+        }
+    }
+
+    #[test]
+    fn detected_symbols_apply_unless_the_file_picks_a_set() {
+        assert_eq!(Theme::default_theme().symbols, Symbols::unicode());
+        let preset = Theme::resolve("monochrome", Symbols::ascii()).unwrap();
+        assert_eq!(preset.symbols, Symbols::ascii());
+
+        let dir = std::env::temp_dir().join(format!("rustical-theme-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("plain.toml");
+        let unicode = dir.join("unicode.toml");
+        std::fs::write(&plain, "prompt = \"bold\"\n").unwrap();
+        std::fs::write(&unicode, "[symbols]\nset = \"unicode\"\n").unwrap();
+        let plain = Theme::resolve(plain.to_str().unwrap(), Symbols::ascii()).unwrap();
+        let unicode = Theme::resolve(unicode.to_str().unwrap(), Symbols::ascii()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(plain.symbols, Symbols::ascii());
+        assert_eq!(unicode.symbols, Symbols::unicode());
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = Theme::from_yaml("prompt: bold\nbogus: red\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
 }